@@ -0,0 +1,159 @@
+use std::path::Path;
+
+pub const DEFAULT_BLOCKCHAIN_FILE: &str = "blockchain.json";
+pub const DEFAULT_ACCOUNT_FILE: &str = "accounts.json";
+const DEFAULT_PORT: u16 = 8080;
+
+pub const USAGE: &str = "\
+Usage: BlockChain [BLOCKCHAIN_FILE] [ACCOUNTS_FILE] [OPTIONS]
+
+Options:
+  --new                 Allow the given files to be created if they do not exist
+  --progress            Print progress while loading and validating
+  --force               Take over a lock left by an instance that is no longer running
+  --observer            Open the chain read-only without any keys
+  --watch               Print new blocks as they are written, until interrupted
+  --yes                 Answer yes to confirmation prompts
+  --no-auto-validator   Do not make the admin of a new chain a validator
+  --dev                 Enable developer commands (e.g. mining empty blocks)
+  --debug               Print the outcome of every transaction signature check
+  --init-accounts-signature
+                        Accept an unsigned accounts file once and sign it on the next save
+  --profile NAME        Use the files saved under a profile name
+  --genesis FILE        Genesis config (JSON) used when creating a new chain
+  --save-retries N      Retry a failed save N times before giving up (default 3)
+  --save-backoff MS     Wait MS milliseconds before the first retry, doubling after each (default 100)
+  --event-log FILE      Record every change made this session to FILE for replay
+  --serve               Answer GET /chain, /receipt/<hash> and /fingerprint on PORT while running
+  --save-profile NAME   Save the given files under a profile name
+  --list-profiles       List saved profiles and exit
+
+Environment:
+  PORT                  Port number to announce and serve on (default 8080)
+  ACCOUNTS_PASSPHRASE   Passphrase used to sign the accounts file";
+
+const BOOLEAN_FLAGS: &[&str] = &["--new", "--progress", "--force", "--observer", "--watch", "--yes", "--no-auto-validator", "--dev", "--debug", "--list-profiles", "--init-accounts-signature", "--serve"];
+
+/// Validated command line and environment settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartupArgs {
+    pub blockchain_file: String,
+    pub account_file: String,
+    pub flags: Vec<String>,
+    pub profile: Option<String>,
+    pub save_profile: Option<String>,
+    pub genesis_file: Option<String>,
+    pub save_retries: Option<u32>,
+    pub save_backoff_ms: Option<u64>,
+    pub event_log_file: Option<String>,
+    pub port: u16,
+}
+
+impl StartupArgs {
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+}
+
+/// Parses the arguments after the program name together with the PORT value
+///
+/// Files named on the command line must already exist unless `--new` is given,
+/// so a mistyped path is reported instead of silently starting a fresh chain.
+pub fn parse_args<I>(raw_args: I, port: Option<&str>) -> Result<StartupArgs, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    // Flags (e.g. --progress) may appear anywhere; the rest are positional file paths
+    let mut flags = Vec::new();
+    let mut files = Vec::new();
+    let mut profile = None;
+    let mut save_profile = None;
+    let mut genesis_file = None;
+    let mut save_retries = None;
+    let mut save_backoff_ms = None;
+    let mut event_log_file = None;
+    let mut raw_args = raw_args.into_iter();
+    while let Some(arg) = raw_args.next() {
+        match arg.as_str() {
+            "--profile" => profile = Some(raw_args.next().ok_or("--profile needs a profile name")?),
+            "--save-profile" => save_profile = Some(raw_args.next().ok_or("--save-profile needs a profile name")?),
+            "--genesis" => genesis_file = Some(raw_args.next().ok_or("--genesis needs a config file")?),
+            "--save-retries" => save_retries = Some(parse_number(&arg, raw_args.next())?),
+            "--save-backoff" => save_backoff_ms = Some(parse_number(&arg, raw_args.next())?),
+            "--event-log" => event_log_file = Some(raw_args.next().ok_or("--event-log needs a file")?),
+            _ if BOOLEAN_FLAGS.contains(&arg.as_str()) => flags.push(arg),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => files.push(arg),
+        }
+    }
+
+    if files.len() > 2 {
+        return Err(format!("Expected at most 2 file arguments, got {}", files.len()));
+    }
+
+    let allow_new = flags.iter().any(|flag| flag == "--new");
+    if !allow_new {
+        if let Some(missing) = files.iter().find(|file| !Path::new(file.as_str()).exists()) {
+            return Err(format!("File {} does not exist (use --new to create it)", missing));
+        }
+    }
+
+    let port = match port {
+        Some(value) => value
+            .trim()
+            .parse::<u16>()
+            .map_err(|_| format!("PORT must be a number between 0 and 65535, got '{}'", value))?,
+        None => DEFAULT_PORT,
+    };
+
+    let mut files = files.into_iter();
+    Ok(StartupArgs {
+        blockchain_file: files.next().unwrap_or_else(|| DEFAULT_BLOCKCHAIN_FILE.to_string()),
+        account_file: files.next().unwrap_or_else(|| DEFAULT_ACCOUNT_FILE.to_string()),
+        flags,
+        profile,
+        save_profile,
+        genesis_file,
+        save_retries,
+        save_backoff_ms,
+        event_log_file,
+        port,
+    })
+}
+
+// Parses the value following a numeric option
+fn parse_number<T: std::str::FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a number", option))?;
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{} must be a non-negative whole number, got '{}'", option, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn flags_and_files_may_be_mixed() {
+        let parsed = parse_args(args(&["chain.json", "--new", "keys.json", "--save-retries", "5"]), Some("9000")).unwrap();
+        assert_eq!(parsed.blockchain_file, "chain.json");
+        assert_eq!(parsed.account_file, "keys.json");
+        assert!(parsed.has_flag("--new"));
+        assert_eq!(parsed.save_retries, Some(5));
+        assert_eq!(parsed.port, 9000);
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        assert!(parse_args(args(&["--bogus"]), None).is_err());
+        assert!(parse_args(args(&["--new", "a", "b", "c"]), None).is_err());
+        assert!(parse_args(args(&["--new"]), Some("99999")).is_err());
+        assert!(parse_args(args(&["--new", "--save-backoff", "-1"]), None).is_err());
+        assert!(parse_args(args(&["definitely-missing-chain-file.json"]), None).is_err());
+    }
+}
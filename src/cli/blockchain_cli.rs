@@ -1,10 +1,168 @@
-use crate::core::Blockchain;
-use crate::models::Transaction;
+use crate::cli::lock::FileLock;
+use crate::cli::profiles;
+use crate::core::blockchain::{keypair_signs_for, SaveRetry, ValidationFailure};
+use crate::core::proof::{verify_header_chain, BalanceAttestation};
+use crate::core::{events, integrity, wallet, Blockchain, BlockchainError, ChainConfig, ConsensusMode, GenesisConfig};
+use crate::models::signature::Signer;
+use crate::models::{Block, BlockHeader, Transaction};
+use crate::network;
 use ed25519_dalek::Keypair;
 use rand::rngs::OsRng;
+use std::fs;
 use std::io::{self, Write};
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// How often long-running operations print their progress
+const PROGRESS_INTERVAL: usize = 1000;
+
+// Prints "<label> done/total" every PROGRESS_INTERVAL items and on completion
+fn progress_printer(label: &'static str) -> impl FnMut(usize, usize) {
+    move |done, total| {
+        if done % PROGRESS_INTERVAL == 0 || done == total {
+            println!("{} {}/{}", label, done, total);
+        }
+    }
+}
+
+// Checks that a file can be written at the given path before saving to it
+fn ensure_writable(path: &str) -> Result<(), String> {
+    let target = Path::new(path);
+    if target.exists() {
+        let metadata = fs::metadata(target).map_err(|e| format!("Cannot access {}: {}", path, e))?;
+        if metadata.permissions().readonly() {
+            return Err(format!("File {} is read-only", path));
+        }
+        return Ok(());
+    }
+    
+    let parent = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let metadata = fs::metadata(parent)
+        .map_err(|_| format!("Directory {} does not exist", parent.display()))?;
+    if !metadata.is_dir() || metadata.permissions().readonly() {
+        return Err(format!("Directory {} is not writable", parent.display()));
+    }
+    Ok(())
+}
+
+// Creates a fresh chain with a newly generated admin account
+//
+// Without a genesis config the admin receives the initial funds; with one, the
+// config decides the allocations and the admin only joins an empty validator set.
+// An existing admin keypair can be passed in to carry it over to the new chain.
+fn create_admin_chain(auto_validator: bool, genesis_file: Option<&str>, admin: Option<Keypair>) -> Result<Blockchain, String> {
+    let admin_keypair = admin.unwrap_or_else(|| Keypair::generate(&mut OsRng));
+    let admin_address = admin_keypair.verifier().address();
+
+    let mut chain = match genesis_file {
+        Some(path) => {
+            let mut genesis = GenesisConfig::load(path)?;
+            if auto_validator && genesis.validators.is_empty() {
+                genesis.validators.push(admin_address.clone());
+            }
+            Blockchain::from_genesis(&genesis)?
+        },
+        None if auto_validator => Blockchain::new(&admin_address),
+        None => {
+            let mut genesis = GenesisConfig::single(&admin_address, 1000.0);
+            genesis.validators.clear();
+            Blockchain::from_genesis(&genesis)?
+        },
+    };
+    // Store public key first
+    chain.public_keys.insert(admin_address.clone(), admin_keypair.public);
+    // Then move the keypair
+    chain.keypairs.insert(admin_address.clone(), Arc::new(admin_keypair));
+    chain.accounts.entry(admin_address.clone()).or_insert(0.0);
+    println!("Created admin account: {}", admin_address);
+    if !chain.validators.values().any(|active| *active) {
+        println!("The chain has no validators; the first account promoted becomes one.");
+    }
+    Ok(chain)
+}
+
+// Asks a yes/no question, treating anything but "y" as no
+fn confirm(question: &str) -> bool {
+    print!("{} (y/N): ", question);
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).unwrap();
+    input.trim().eq_ignore_ascii_case("y")
+}
+
+// Moves an existing file aside to "<path>.<unix seconds>.bak", returning the new path
+fn archive_file(path: &str) -> Result<Option<String>, String> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs();
+    let archived = format!("{}.{}.bak", path, seconds);
+    fs::rename(path, &archived).map_err(|e| format!("Failed to archive {}: {}", path, e))?;
+    Ok(Some(archived))
+}
+
+// Reads a serialized transaction written by the offline signing commands
+fn read_transaction_file(path: &str) -> Result<Transaction, String> {
+    let data = fs::read_to_string(path).map_err(|_| format!("Failed to read transaction file: {}", path))?;
+    serde_json::from_str(&data).map_err(|e| format!("Failed to parse transaction file: {}", e))
+}
+
+// Text that must be typed to confirm archiving the chain and starting a new one
+const RESET_CONFIRMATION: &str = "RESET";
+
+// Menu options that change the chain, accounts or files (unavailable to observers)
+const MUTATING_OPTIONS: &[&str] = &["1", "5", "7", "8", "11", "13", "16", "17", "19", "21", "25", "26", "28", "33", "34", "35", "37", "43", "44", "45", "48", "49", "53", "57", "61"];
+
+// Outcome and timing of one full chain validation
+struct ValidationBenchmark {
+    blocks: usize,
+    transactions: usize,
+    elapsed: Duration,
+    result: Result<(), ValidationFailure>,
+}
+
+impl ValidationBenchmark {
+    // Blocks and transactions validated per second
+    fn throughput(&self) -> (f64, f64) {
+        let seconds = self.elapsed.as_secs_f64().max(f64::EPSILON);
+        (self.blocks as f64 / seconds, self.transactions as f64 / seconds)
+    }
+}
+
+// Startup options for the CLI
+#[derive(Default)]
+pub struct CliOptions {
+    pub show_progress: bool,
+    pub force: bool,
+    pub accounts_passphrase: Option<String>,
+    /// Accept an accounts file that has not been signed yet, signing it on the next save
+    pub init_accounts_signature: bool,
+    pub observer: bool,
+    /// Named profile whose files replace the positional file paths
+    pub profile: Option<String>,
+    /// Answer yes to confirmation prompts (for scripted use)
+    pub assume_yes: bool,
+    /// Leave a newly created admin account out of the validator set
+    pub no_auto_validator: bool,
+    /// Enable developer-only commands
+    pub dev: bool,
+    /// Print why each transaction passes or fails signature checks during validation
+    pub verbose_validation: bool,
+    /// Genesis config used when a new chain is created
+    pub genesis_file: Option<String>,
+    /// Retry policy for transient failures while saving
+    pub save_retry: SaveRetry,
+    /// File that receives every change made this session, for replaying bug reports
+    pub event_log_file: Option<String>,
+}
 
 // CLI manager
 pub struct BlockchainCLI {
@@ -12,72 +170,252 @@ pub struct BlockchainCLI {
     current_user: Option<String>,
     blockchain_file: String,
     account_file: String,
+    show_progress: bool,
+    observer: bool,
+    assume_yes: bool,
+    auto_validator: bool,
+    genesis_file: Option<String>,
+    dev: bool,
+    event_log_file: Option<String>,
+    // Events already written to the event log file
+    persisted_events: usize,
+    _lock: Option<FileLock>,
 }
 
 impl BlockchainCLI {
-    pub fn new(blockchain_file: &str, accounts_file: &str) -> Self {
-        let blockchain = if Path::new(blockchain_file).exists() {
-            match Blockchain::load_from_file(blockchain_file, accounts_file) {
+    pub fn new(blockchain_file: &str, accounts_file: &str, options: CliOptions) -> Result<Self, String> {
+        let show_progress = options.show_progress;
+    
+        // A named profile takes precedence over the positional file paths
+        let profile = match &options.profile {
+            Some(name) => {
+                let profile = profiles::resolve(name)?;
+                println!("Using profile '{}': {} / {}", name, profile.blockchain_file, profile.accounts_file);
+                Some(profile)
+            },
+            None => None,
+        };
+        let (blockchain_file, accounts_file) = match &profile {
+            Some(profile) => (profile.blockchain_file.as_str(), profile.accounts_file.as_str()),
+            None => (blockchain_file, accounts_file),
+        };
+    
+        // Observers only read the chain, so they neither lock nor need any keys
+        if options.observer {
+            let mut blockchain = Blockchain::load_read_only(blockchain_file)?;
+            blockchain.verbose_validation = options.verbose_validation;
+            println!("Observer mode: loaded {} blocks read-only", blockchain.chain.len());
+            return Ok(BlockchainCLI {
+                blockchain,
+                current_user: None,
+                blockchain_file: blockchain_file.to_string(),
+                account_file: accounts_file.to_string(),
+                show_progress,
+                observer: true,
+                assume_yes: options.assume_yes,
+                auto_validator: !options.no_auto_validator,
+                genesis_file: options.genesis_file,
+                dev: options.dev,
+                event_log_file: None,
+                persisted_events: 0,
+                _lock: None,
+            });
+        }
+    
+        // Keep other node processes away from the same data files
+        let lock = FileLock::acquire(blockchain_file, options.force)?;
+    
+        // Loading refuses an accounts file modified outside this program. An unsigned one is
+        // accepted here instead when signing is being enabled for the first time
+        let mut passphrase = options.accounts_passphrase.as_deref();
+        if options.init_accounts_signature && Path::new(accounts_file).exists() {
+            if let Some(passphrase) = passphrase.take() {
+                integrity::verify_accounts_file(accounts_file, passphrase, true)?;
+            }
+        }
+    
+        let mut read_only = false;
+        let mut blockchain = if Path::new(blockchain_file).exists() {
+            let loaded = if show_progress {
+                let mut progress = progress_printer("Validated blocks");
+                Blockchain::load_from_file_with_progress(blockchain_file, accounts_file, passphrase, &mut progress)
+            } else {
+                Blockchain::load_from_file(blockchain_file, accounts_file, passphrase)
+            };
+            match loaded {
                 Ok(chain) => {
                     println!("Loaded existing blockchain with {} blocks", chain.chain.len());
                     chain
                 },
-                Err(e) => {
-                    println!("Error loading blockchain: {}. Creating new one.", e);
-                    let mut csprng = OsRng;
-                    let admin_keypair = Keypair::generate(&mut csprng);
-                    let admin_address = hex::encode(admin_keypair.public.as_bytes());
-                    let mut chain = Blockchain::new(&admin_address);
-                    // Store public key first
-                    chain.public_keys.insert(admin_address.clone(), admin_keypair.public);
-                    // Then move the keypair
-                    chain.keypairs.insert(admin_address.clone(), Arc::new(admin_keypair));
-                    chain.validators.insert(admin_address.clone(), true);
-                    chain
-                }
+                Err(e) => match Blockchain::load_read_only(blockchain_file) {
+                    // The chain itself is fine; never replace it just because the keys are missing
+                    Ok(chain) => {
+                        println!("WARNING: {}", e);
+                        println!("WARNING: Loaded {} READ-ONLY without signing keys.", blockchain_file);
+                        println!("WARNING: Restore the accounts file and restart to make changes.");
+                        read_only = true;
+                        chain
+                    },
+                    // An unreadable chain is never replaced; the user decides what to do with it
+                    Err(_) => {
+                        return Err(format!(
+                            "Error loading blockchain: {}. Move {} aside to start a new chain.",
+                            e, blockchain_file
+                        ))
+                    }
+                },
             }
         } else {
             println!("Creating new blockchain...");
-            let mut csprng = OsRng;
-            let admin_keypair = Keypair::generate(&mut csprng);
-            let admin_address = hex::encode(admin_keypair.public.as_bytes());
-            let mut chain = Blockchain::new(&admin_address);
-            // Store public key first
-            chain.public_keys.insert(admin_address.clone(), admin_keypair.public);
-            // Then move the keypair
-            chain.keypairs.insert(admin_address.clone(), Arc::new(admin_keypair));
-            chain.validators.insert(admin_address.clone(), true);
-            println!("Created admin account: {}", admin_address);
-            chain
+            create_admin_chain(!options.no_auto_validator, options.genesis_file.as_deref(), None)?
         };
 
-        BlockchainCLI {
+        blockchain.accounts_passphrase = options.accounts_passphrase;
+        blockchain.save_retry = options.save_retry;
+        blockchain.dev_mode = options.dev;
+        blockchain.verbose_validation = options.verbose_validation;
+        if options.event_log_file.is_some() {
+            blockchain.record_events(Vec::new())?;
+        }
+    
+        Ok(BlockchainCLI {
             blockchain,
             current_user: None,
             blockchain_file: blockchain_file.to_string(),
             account_file: accounts_file.to_string(),
-        }
+            show_progress,
+            observer: read_only,
+            assume_yes: options.assume_yes,
+            auto_validator: !options.no_auto_validator,
+            genesis_file: options.genesis_file,
+            dev: options.dev,
+            event_log_file: options.event_log_file,
+            persisted_events: 0,
+            _lock: Some(lock),
+        })
     }
     
     pub fn save_blockchain(&self) -> Result<(), String> {
+        if self.observer {
+            return Err("Observer mode is read-only".to_string());
+        }
         self.blockchain.save_to_file(&self.blockchain_file, &self.account_file)
     }
     
+    /// Archives the current files and starts a fresh chain, optionally keeping the current user as admin
+    ///
+    /// Nothing changes unless `confirmation` is exactly `RESET`.
+    pub fn reset_chain(&mut self, confirmation: &str, reuse_admin: bool) -> Result<Vec<String>, String> {
+        if confirmation != RESET_CONFIRMATION {
+            return Err(format!("Reset not confirmed; type {} to start a new chain", RESET_CONFIRMATION));
+        }
+
+        let admin = if reuse_admin {
+            let address = self.get_current_user()?;
+            let keypair = self.blockchain.keypairs.get(&address)
+                .ok_or_else(|| "Keypair not found for current account".to_string())?;
+            Some(Keypair::from_bytes(&keypair.to_bytes()).map_err(|e| e.to_string())?)
+        } else {
+            None
+        };
+
+        // Build the new chain first so a bad genesis config leaves the old files in place
+        let mut blockchain = create_admin_chain(self.auto_validator, self.genesis_file.as_deref(), admin)?;
+        blockchain.accounts_passphrase = self.blockchain.accounts_passphrase.clone();
+        blockchain.save_retry = self.blockchain.save_retry;
+        blockchain.dev_mode = self.blockchain.dev_mode;
+        blockchain.verbose_validation = self.blockchain.verbose_validation;
+        if let Some(events) = self.blockchain.take_event_log() {
+            blockchain.record_events(events)?;
+        }
+
+        let mut archived = Vec::new();
+        for path in [&self.blockchain_file, &self.account_file] {
+            archived.extend(archive_file(path)?);
+        }
+        self.blockchain = blockchain;
+        self.current_user = None;
+        self.save_blockchain()?;
+        Ok(archived)
+    }
+
+    /// Applies a change to the chain and saves it, keeping the previous state if either fails
+    fn commit<T>(&mut self, change: impl FnOnce(&mut Blockchain) -> Result<T, String>) -> Result<T, String> {
+        if self.observer {
+            return Err("Observer mode is read-only".to_string());
+        }
+        self.blockchain.commit(&self.blockchain_file, &self.account_file, change)
+    }
+
+    pub fn save_as(&mut self, blockchain_path: &str, accounts_path: &str, switch_files: bool) -> Result<(), String> {
+        ensure_writable(blockchain_path)?;
+        ensure_writable(accounts_path)?;
+
+        // The lock follows the active files, so take the new one before writing there
+        let lock = if switch_files { Some(FileLock::acquire(blockchain_path, false)?) } else { None };
+        self.blockchain.save_to_file(blockchain_path, accounts_path)?;
+        if let Some(lock) = lock {
+            self.blockchain_file = blockchain_path.to_string();
+            self.account_file = accounts_path.to_string();
+            self._lock = Some(lock);
+        }
+        Ok(())
+    }
+    
+    pub fn export_wallet(&self, path: &str, passphrase: &str) -> Result<usize, String> {
+        let keypairs = self.blockchain.encoded_keypairs();
+        let bundle = wallet::encrypt_wallet(&keypairs, passphrase)?;
+        fs::write(path, bundle).map_err(|e| format!("Failed to write wallet: {}", e))?;
+        Ok(keypairs.len())
+    }
+    
+    pub fn import_wallet(&mut self, path: &str, passphrase: &str) -> Result<usize, String> {
+        let bundle = fs::read_to_string(path)
+            .map_err(|_| format!("Failed to read wallet file: {}", path))?;
+        let keypairs = wallet::decrypt_wallet(&bundle, passphrase)?;
+    
+        // One corrupted key rejects the whole wallet before anything is registered
+        for keypair in &keypairs {
+            let address = keypair.verifier().address();
+            if !keypair_signs_for(keypair, &address) {
+                return Err(format!("Wallet key for {} does not match its public key", address));
+            }
+        }
+
+        let count = keypairs.len();
+        for keypair in keypairs {
+            self.blockchain.register_keypair(keypair);
+        }
+        self.save_blockchain()?;
+        Ok(count)
+    }
+    
+    pub fn rewrite_accounts_file(&self) -> Result<(), String> {
+        self.blockchain.save_accounts_to_file(&self.account_file)
+    }
+    
     pub fn create_new_account(&mut self) -> String {
         let mut csprng = OsRng;
         let keypair = Keypair::generate(&mut csprng);
-        let address = self.blockchain.register_keypair(keypair);
-        address
+        self.blockchain.register_keypair(keypair)
     }
     
     pub fn select_account(&mut self, address: &str) -> Result<(), String> {
-        if !self.blockchain.keypairs.contains_key(address) {
+        if !self.list_accounts().iter().any(|account| account == address) {
             return Err(format!("Account {} not found", address));
         }
+        if !self.observer && !self.blockchain.verify_keypair(address) {
+            return Err(format!("Stored key for {} does not match the address; the accounts file may be corrupted", address));
+        }
         self.current_user = Some(address.to_string());
         Ok(())
     }
     
+    /// Path of the chain file in use, after any profile has been applied
+    pub fn blockchain_file(&self) -> &str {
+        &self.blockchain_file
+    }
+
     pub fn get_current_user(&self) -> Result<String, &'static str> {
         match &self.current_user {
             Some(address) => Ok(address.clone()),
@@ -86,27 +424,141 @@ impl BlockchainCLI {
     }
     
     pub fn list_accounts(&self) -> Vec<String> {
+        // Observers hold no keys, so they browse every account on the chain
+        if self.observer {
+            return self.blockchain.accounts.keys().cloned().collect();
+        }
         self.blockchain.keypairs.keys().cloned().collect()
     }
     
-    pub fn create_transaction(&mut self, recipient: &str, amount: f64) -> Result<(), String> {
+    pub fn create_transaction(&mut self, recipient: &str, amount: f64, fee: f64, memo: &str) -> Result<(), String> {
         let sender = self.get_current_user()?;
     
         if !self.blockchain.accounts.contains_key(recipient) {
             return Err(format!("Recipient {} not found", recipient));
         }
     
-        let transaction = Transaction::new(sender.clone(), recipient.to_string(), amount);
+        let transaction = Transaction::new(sender.clone(), recipient.to_string(), amount)
+            .with_fee(fee)
+            .with_memo(memo);
+        let tx_hash = transaction.calculate_hash();
     
         let keypair = self.blockchain.keypairs.get(&sender)
             .cloned() // Now possible since it's an Arc<Keypair>
             .ok_or_else(|| "Keypair not found for sender".to_string())?;
     
-        self.blockchain.add_transaction(transaction, &keypair)?;
+        self.blockchain
+            .add_transaction(transaction, keypair.as_ref())
+            .map_err(|e| e.to_string())?;
+
+        // Let the user know once their transaction is mined
+        self.blockchain.watch_transaction(&tx_hash, Box::new(|tx, block| {
+            println!(
+                "Transaction of {:.2} to {} confirmed in block #{} ({})",
+                tx.amount, tx.recipient, block.index, block.hash
+            );
+        }));
     
         Ok(())
     }
     
+    /// Projected (sender, recipient) balances if the transaction were mined
+    pub fn preview_transaction(&self, recipient: &str, amount: f64, fee: f64, memo: &str) -> Result<(f64, f64), String> {
+        let sender = self.get_current_user()?;
+        let transaction = Transaction::new(sender, recipient.to_string(), amount)
+            .with_fee(fee)
+            .with_memo(memo);
+        self.blockchain.simulate(&transaction).map_err(|e| e.to_string())
+    }
+
+    /// Checks whether the current user can afford a batch of (recipient, amount, fee) transfers
+    ///
+    /// Returns the total required and the balance available to spend.
+    pub fn plan_transfers(&self, transfers: &[(String, f64, f64)]) -> Result<(f64, f64), String> {
+        let sender = self.get_current_user()?;
+        let transactions: Vec<Transaction> = transfers
+            .iter()
+            .map(|(recipient, amount, fee)| {
+                Transaction::new(sender.clone(), recipient.clone(), *amount).with_fee(*fee)
+            })
+            .collect();
+        Ok((
+            self.blockchain.required_balance(&transactions),
+            self.blockchain.spendable_balance(&sender),
+        ))
+    }
+
+    /// Writes a transaction awaiting signature so it can be signed on another machine
+    pub fn export_unsigned_transaction(&self, sender: &str, recipient: &str, amount: f64, fee: f64, memo: &str, path: &str) -> Result<(), String> {
+        for address in [sender, recipient] {
+            if !self.blockchain.accounts.contains_key(address) {
+                return Err(format!("Account {} not found", address));
+            }
+        }
+    
+        let transaction = Transaction::new(sender.to_string(), recipient.to_string(), amount)
+            .with_fee(fee)
+            .with_memo(memo);
+        let data = serde_json::to_string_pretty(&transaction)
+            .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
+        fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+    
+    /// Signs a transaction file in place with the sender's locally held key
+    pub fn sign_transaction_file(&self, path: &str) -> Result<(), String> {
+        let mut transaction = read_transaction_file(path)?;
+        let keypair = self.blockchain.keypairs.get(&transaction.sender)
+            .cloned()
+            .ok_or_else(|| format!("No keypair held for sender {}", transaction.sender))?;
+    
+        transaction.sign(keypair.as_ref())?;
+        let data = serde_json::to_string_pretty(&transaction)
+            .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
+        fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+    
+    /// Writes the current user's signed balance attestation to a file
+    pub fn export_balance_attestation(&self, path: &str) -> Result<(), String> {
+        let address = self.get_current_user()?;
+        let keypair = self.blockchain.keypairs.get(&address)
+            .cloned()
+            .ok_or_else(|| "Keypair not found for current account".to_string())?;
+
+        let attestation = self.blockchain.sign_balance_attestation(keypair.as_ref())?;
+        let data = serde_json::to_string_pretty(&attestation)
+            .map_err(|e| format!("Failed to serialize attestation: {}", e))?;
+        fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path, e))
+    }
+
+    /// Checks an attestation file against this chain
+    pub fn verify_balance_attestation(&self, path: &str) -> Result<BalanceAttestation, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let attestation: BalanceAttestation = serde_json::from_str(&data)
+            .map_err(|e| format!("Failed to parse attestation: {}", e))?;
+        self.blockchain.verify_balance_attestation(&attestation)?;
+        Ok(attestation)
+    }
+
+    /// Validates a transaction signed elsewhere and adds it to the mempool
+    pub fn submit_from_file(&mut self, path: &str) -> Result<(), String> {
+        let transaction = read_transaction_file(path)?;
+        self.blockchain
+            .submit_signed_transaction(transaction)
+            .map_err(|e| e.to_string())?;
+        self.save_blockchain()
+    }
+    
+    /// Merges the pending transactions of another node's blockchain file into this mempool
+    pub fn merge_mempool_from_file(&mut self, path: &str) -> Result<(usize, usize), String> {
+        let other = Blockchain::load_read_only(path)?;
+        let offered = other.pending_transactions.len();
+        let admitted = self.blockchain.merge_mempool(&other.pending_transactions);
+        if admitted > 0 {
+            self.save_blockchain()?;
+        }
+        Ok((admitted, offered))
+    }
+    
     pub fn create_new_block(&mut self) -> Result<(), String> {
         let validator = self.get_current_user()?;
         
@@ -114,83 +566,652 @@ impl BlockchainCLI {
             return Err("Current account is not a validator".to_string());
         }
         
-        self.blockchain.create_block(&validator)?;
-        self.save_blockchain()?;
+        let block = self.commit(|chain| chain.create_block(&validator))?;
+    
+        for tx in &block.transactions {
+            if let Some(receipt) = self.blockchain.receipt(&tx.calculate_hash()) {
+                println!("{}", receipt);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Validates a single block from a JSON file and appends it to the chain
+    pub fn import_block_from_file(&mut self, path: &str) -> Result<u32, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let block: Block = serde_json::from_str(&data)
+            .map_err(|e| format!("Failed to parse block: {}", e))?;
+        let index = block.index;
+        self.commit(|chain| chain.append_block(block).map_err(|e| e.to_string()))?;
+        Ok(index)
+    }
+
+    pub fn export_ndjson(&self, path: &str) -> Result<usize, String> {
+        let file = fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+        self.blockchain.export_ndjson(io::BufWriter::new(file))
+    }
+
+    /// Writes every block header to a JSON file for light clients, returning the header count
+    pub fn export_headers(&self, path: &str) -> Result<usize, String> {
+        let headers = self.blockchain.export_headers();
+        let data = serde_json::to_string_pretty(&headers)
+            .map_err(|e| format!("Failed to serialize headers: {}", e))?;
+        fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        Ok(headers.len())
+    }
+
+    /// Checks that a header file links up and is signed by this chain's validators, then
+    /// compares every header with this chain
+    pub fn verify_header_file(&self, path: &str) -> Result<(), String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let headers: Vec<BlockHeader> = serde_json::from_str(&data)
+            .map_err(|e| format!("Failed to parse headers: {}", e))?;
+        // Retired validators signed the older headers, so they count too
+        let validators: Vec<String> = self.blockchain.validators.keys().cloned().collect();
+        verify_header_chain(&headers, &validators)?;
+
+        let tip = headers.last().expect("verified header chains are not empty");
+        println!("Header chain links up and is signed by known validators: {} headers, tip #{} {}", headers.len(), tip.index, tip.hash);
+
+        let ours = self.blockchain.export_headers();
+        let differs = headers
+            .iter()
+            .find(|header| ours.get(header.index as usize).is_some_and(|our_header| our_header != *header));
+        match differs {
+            Some(header) => println!("Differs from this chain at block #{}", header.index),
+            None if tip.index as usize >= self.blockchain.chain.len() => println!(
+                "Matches this chain up to its tip (#{}); later headers are unverified",
+                self.blockchain.get_latest_block().index
+            ),
+            None => println!("Matches this chain at every header"),
+        }
+        Ok(())
+    }
+
+    pub fn import_ndjson(&mut self, path: &str) -> Result<usize, String> {
+        let file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        self.commit(|chain| chain.import_ndjson(io::BufReader::new(file)))
+    }
+
+    pub fn print_block_preview(&self) -> Result<(), String> {
+        let validator = self.get_current_user()?;
+        let block = self.blockchain.preview_block(&validator).map_err(|e| e.to_string())?;
+    
+        println!("Preview (not committed)");
+        println!("{}", block);
+        for tx in &block.transactions {
+            println!("    {} -> {}: {:.2} (fee {:.2})", tx.sender, tx.recipient, tx.amount, tx.fee);
+        }
+        let fees: f64 = block.transactions.iter().map(|tx| tx.fee).sum();
+        println!("Fees to collect: {:.2}", fees);
+        println!("Expected revenue (fees and reward): {:.2}", self.blockchain.next_block_revenue());
         Ok(())
     }
     
+    /// Replaces the chain's policy parameters with those in a JSON file; validators only
+    pub fn load_config(&mut self, path: &str) -> Result<(), String> {
+        let current_user = self.get_current_user()?;
+        if !self.blockchain.is_validator(&current_user) {
+            return Err("Only validators can change the chain configuration".to_string());
+        }
+        let config = ChainConfig::load(path)?;
+        self.commit(|chain| chain.set_config(config))
+    }
+
     pub fn promote_to_validator(&mut self, address: &str) -> Result<(), String> {
+        self.check_can_promote()?;
+        self.commit(|chain| chain.add_validator(address.to_string()))
+    }
+
+    /// Promotes several accounts at once, returning each address's outcome
+    ///
+    /// With `atomic` set, nothing is promoted unless every address can be.
+    pub fn promote_validators(&mut self, addresses: &[String], atomic: bool) -> Result<Vec<Result<(), BlockchainError>>, String> {
+        self.check_can_promote()?;
+        self.commit(|chain| Ok(chain.add_validators(addresses, atomic)))
+    }
+
+    fn check_can_promote(&self) -> Result<(), String> {
         let current_user = self.get_current_user()?;
         
-        // Check if current user is a validator (only validators can promote)
-        if !self.blockchain.is_validator(&current_user) {
+        // Check if current user is a validator (only validators can promote),
+        // except to bootstrap the first validator of a chain created without one
+        let has_validators = self.blockchain.validators.values().any(|active| *active);
+        if has_validators && !self.blockchain.is_validator(&current_user) {
             return Err("Only validators can promote accounts".to_string());
         }
-        
-        self.blockchain.add_validator(address.to_string())?;
-        self.save_blockchain()?;
         Ok(())
     }
     
+    pub fn transfer_validator_role(&mut self, to: &str) -> Result<(), String> {
+        let current_user = self.get_current_user()?;
+        
+        self.commit(|chain| chain.transfer_validator_role(&current_user, to))
+    }
+    
+    pub fn drop_orphaned_keypairs(&mut self) -> Result<usize, String> {
+        let orphans = self.blockchain.orphaned_keypairs();
+        self.commit(|chain| {
+            for address in &orphans {
+                chain.keypairs.remove(address);
+                chain.public_keys.remove(address);
+            }
+            Ok(())
+        })?;
+        if self.current_user.as_ref().is_some_and(|user| orphans.contains(user)) {
+            self.current_user = None;
+        }
+        Ok(orphans.len())
+    }
+    
     pub fn print_balance(&self) -> Result<(), String> {
         let address = self.get_current_user()?;
         let balance = self.blockchain.get_account_balance(&address);
         println!("Balance for {}: {:.2}", address, balance);
+        let locked = self.blockchain.immature_rewards(&address);
+        if locked > 0.0 {
+            println!("Locked rewards (not yet mature): {:.2}", locked);
+            println!("Spendable: {:.2}", self.blockchain.spendable_balance(&address));
+        }
         Ok(())
     }
     
     pub fn print_pending_transactions(&self) {
         println!("Pending Transactions: {}", self.blockchain.pending_transactions.len());
-        for (i, tx) in self.blockchain.pending_transactions.iter().enumerate() {
+        for (i, validity) in self.blockchain.pending_validity() {
             println!("Transaction #{}", i + 1);
-            println!("{}", tx);
+            println!("{}", self.blockchain.pending_transactions[i]);
+            if let Err(e) = validity {
+                println!("Would be dropped at the next block: {}", e);
+            }
             println!("--------------------");
         }
     }
     
+    pub fn print_pending_claims(&self) {
+        if self.blockchain.pending_claims.is_empty() {
+            println!("No transfers are waiting to be claimed");
+            return;
+        }
+        let height = self.blockchain.chain.len() as u32;
+        for claim in &self.blockchain.pending_claims {
+            println!("Transfer {}", claim.tx_hash);
+            println!("  {:.2} from {} to {}", claim.amount, claim.sender, claim.recipient);
+            println!(
+                "  Held since block #{}, returns to sender at block #{} (next block is #{})",
+                claim.block_index,
+                self.blockchain.claim_expiry(claim),
+                height
+            );
+        }
+    }
+
+    /// Queues a claim for a transfer held for the current user
+    pub fn claim_transfer(&mut self, tx_hash: &str, fee: f64) -> Result<(), String> {
+        let claimant = self.get_current_user()?;
+        let keypair = self.blockchain.keypairs.get(&claimant)
+            .cloned()
+            .ok_or_else(|| "Keypair not found for claimant".to_string())?;
+
+        self.blockchain
+            .claim_transfer(tx_hash, fee, keypair.as_ref())
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn print_pending_balances(&self) {
+        let deltas = self.blockchain.pending_deltas();
+        if deltas.is_empty() {
+            println!("No accounts with pending activity");
+            return;
+        }
+        for (address, delta) in deltas {
+            let confirmed = self.blockchain.get_account_balance(&address);
+            println!(
+                "{}: confirmed {:.2}, pending {:+.2}, projected {:.2}",
+                address, confirmed, delta, confirmed + delta
+            );
+        }
+    }
+    
+    /// Lists pending transactions per sender, busiest senders first
+    pub fn print_pending_by_sender(&self) {
+        let mut groups: Vec<_> = self.blockchain.pending_by_sender().into_iter().collect();
+        if groups.is_empty() {
+            println!("No pending transactions");
+            return;
+        }
+        groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+        for (sender, transactions) in groups {
+            let total: f64 = transactions.iter().map(|tx| tx.amount + tx.fee).sum();
+            println!("{}: {} pending, {:.2} including fees", sender, transactions.len(), total);
+            let spendable = self.blockchain.spendable_balance(&sender);
+            if sender != "0" && total > spendable {
+                println!("  WARNING: exceeds spendable balance of {:.2}", spendable);
+            }
+            for tx in transactions {
+                println!("  {:.2} (fee {:.2}) to {} at {}", tx.amount, tx.fee, tx.recipient, tx.timestamp);
+            }
+        }
+    }
+
+    /// Writes the recorded events to the event log file if any were added since the last write
+    fn save_event_log(&mut self) -> Result<(), String> {
+        let (Some(path), Some(events)) = (&self.event_log_file, self.blockchain.event_log()) else {
+            return Ok(());
+        };
+        if events.len() != self.persisted_events {
+            events::write_event_log(path, events)?;
+            self.persisted_events = events.len();
+        }
+        Ok(())
+    }
+
+    /// Replays an event log into a fresh chain and compares the result with this one
+    pub fn replay_event_log(&self, path: &str) -> Result<(), String> {
+        let events = events::read_event_log(path)?;
+        let replayed = Blockchain::replay(&events)?;
+        println!("Replayed {} events into a chain of {} blocks", events.len(), replayed.chain.len());
+        println!("Fingerprint: {}", replayed.fingerprint());
+        println!("State root: {}", replayed.state_root());
+        println!("Pending transactions: {}", replayed.pending_transactions.len());
+        let matches = replayed.fingerprint() == self.blockchain.fingerprint()
+            && replayed.state_root() == self.blockchain.state_root();
+        println!("Matches the current chain: {}", if matches { "yes" } else { "no" });
+        Ok(())
+    }
+
+    /// Lists pending transactions that will not make it into the next few blocks, with the fee each needs
+    pub fn print_stuck_transactions(&self, blocks_threshold: u32) {
+        let stuck = self.blockchain.stuck_transactions(blocks_threshold);
+        if stuck.is_empty() {
+            println!("No pending transactions are stuck beyond {} block(s)", blocks_threshold);
+            return;
+        }
+        println!("{} transaction(s) would wait more than {} block(s):", stuck.len(), blocks_threshold);
+        for tx in stuck {
+            println!("{}", tx.calculate_hash());
+            println!("  {:.2} from {} to {} (fee {:.2})", tx.amount, tx.sender, tx.recipient, tx.fee);
+            match self.blockchain.suggested_fee(tx, blocks_threshold) {
+                Some(fee) => println!("  Resend with a fee of at least {:.2} to be included in time", fee),
+                None => println!("  No allowed fee gets it included in time"),
+            }
+        }
+    }
+
+    pub fn print_diagnosis(&self) {
+        let problems = self.blockchain.diagnose();
+        if problems.is_empty() {
+            println!("No problems found in {} blocks", self.blockchain.chain.len());
+            return;
+        }
+        println!("Found {} problem(s):", problems.len());
+        for problem in problems {
+            println!("{}", problem);
+            println!("  Suggestion: {}", problem.suggestion());
+        }
+    }
+    
+    pub fn print_activity(&self, bucket_seconds: u64) {
+        let buckets = self.blockchain.timeseries(bucket_seconds);
+        println!("Activity per {} seconds", bucket_seconds);
+        for bucket in buckets {
+            println!(
+                "{}: {} transactions, volume {:.2}",
+                bucket.start, bucket.transaction_count, bucket.volume
+            );
+        }
+    }
+    
+    pub fn print_reconciliation(&self) {
+        let report = self.blockchain.reconcile_accounts();
+        let sections = [
+            ("Chain addresses without a keypair", &report.missing_keypairs),
+            ("Keypairs not on the chain", &report.orphaned_keypairs),
+            ("Keys that do not match their address", &report.derivation_mismatches),
+        ];
+        for (title, addresses) in sections {
+            println!("{}: {}", title, addresses.len());
+            for address in addresses {
+                println!("  {}", address);
+            }
+        }
+    }
+    
     pub fn print_blockchain_status(&self) {
         println!("Blockchain Status");
         println!("----------------");
         println!("Blocks: {}", self.blockchain.chain.len());
         println!("Accounts: {}", self.blockchain.accounts.len());
         println!("Validators: {}", self.blockchain.validators.len());
+        println!("Consensus: {:?}", self.blockchain.config().consensus_mode);
+        match self.blockchain.next_block_producer() {
+            Some(producer) => println!("Next producer: {}", producer),
+            None => println!("Next producer: any validator"),
+        }
+        if self.blockchain.config().consensus_mode == ConsensusMode::StakeWeighted {
+            println!("Consensus seed: {}", hex::encode(self.blockchain.consensus_seed()));
+        }
         println!("Pending Transactions: {}", self.blockchain.pending_transactions.len());
+        println!("State Root: {}", self.blockchain.state_root());
+        println!("Chain Fingerprint: {}", self.blockchain.fingerprint());
+        
+        let validity = if self.show_progress {
+            let mut progress = progress_printer("Validated blocks");
+            self.blockchain.validate_chain_with_progress(&mut progress)
+        } else {
+            self.blockchain.validate_chain_detailed()
+        };
+        match validity {
+            Ok(()) => println!("Chain Validity: Valid"),
+            Err(failure) => println!("Chain Validity: INVALID ({})", failure),
+        }
         
-        let is_valid = self.blockchain.validate_chain();
-        println!("Chain Validity: {}", if is_valid { "Valid" } else { "INVALID" });
+        let is_strictly_valid = self.blockchain.validate_chain_strict();
+        println!("Strict Validity: {}", if is_strictly_valid { "Valid" } else { "INVALID" });
+        for warning in self.blockchain.validation_warnings() {
+            println!("  Warning: {}", warning);
+        }
         
         println!("\nLatest Block:");
         println!("{}", self.blockchain.get_latest_block());
     }
     
-    pub fn run(&mut self) {
-        println!("Welcome to Private Blockchain CLI");
-        println!("--------------------------------");
-        
-        loop {
-            // Display current status
-            if let Some(address) = &self.current_user {
-                let balance = self.blockchain.get_account_balance(address);
-                let is_validator = self.blockchain.is_validator(address);
-                println!("\nCurrent Account: {} (Balance: {:.2}) [{}]", 
-                    address, 
-                    balance,
-                    if is_validator { "Validator" } else { "User" }
-                );
-            } else {
-                println!("\nNo account selected");
+    pub fn benchmark_validation(&self) {
+        let benchmark = self.time_validation();
+        match &benchmark.result {
+            Ok(()) => println!("Chain Validity: Valid"),
+            Err(failure) => println!("Chain Validity: INVALID ({})", failure),
+        }
+        let (blocks_per_second, transactions_per_second) = benchmark.throughput();
+        println!(
+            "Validated {} blocks and {} transactions in {:.3}s",
+            benchmark.blocks, benchmark.transactions, benchmark.elapsed.as_secs_f64()
+        );
+        println!("Throughput: {:.1} blocks/s, {:.1} transactions/s", blocks_per_second, transactions_per_second);
+    }
+
+    /// Runs a full validation, timing it against the chain's block and transaction counts
+    fn time_validation(&self) -> ValidationBenchmark {
+        let started = Instant::now();
+        let result = self.blockchain.validate_chain_detailed();
+        ValidationBenchmark {
+            blocks: self.blockchain.chain.len(),
+            transactions: self.blockchain.chain.iter().map(|block| block.transactions.len()).sum(),
+            elapsed: started.elapsed(),
+            result,
+        }
+    }
+
+    pub fn print_genesis(&self) {
+        match self.blockchain.chain.first() {
+            Some(genesis) => {
+                println!("Genesis Block");
+                println!("-------------");
+                println!("{}", genesis);
+                println!("\nInitial Distribution:");
+                for (address, amount) in self.blockchain.genesis_distribution() {
+                    println!("{}: {:.2}", address, amount);
+                }
+            },
+            None => println!("Chain has no genesis block"),
+        }
+    }
+    
+    pub fn import_chain_from_file(&mut self, path: &str) -> Result<bool, String> {
+        let data = fs::read_to_string(path)
+            .map_err(|_| format!("Failed to read blockchain file: {}", path))?;
+        let candidate: Blockchain = serde_json::from_str(&data)
+            .map_err(|_| "Failed to parse blockchain file".to_string())?;
+    
+        let replaced = self.blockchain.try_replace_chain(candidate.chain)?;
+        if replaced {
+            self.save_blockchain()?;
+        }
+        Ok(replaced)
+    }
+    
+    pub fn print_account_info(&self) -> Result<(), String> {
+        let address = self.get_current_user()?;
+    
+        println!("Account: {}", address);
+        println!("Balance: {:.2}", self.blockchain.get_account_balance(&address));
+        println!("Role: {}", if self.blockchain.is_validator(&address) { "Validator" } else { "User" });
+        match self.blockchain.last_activity(&address) {
+            Some(index) => println!("Last active: block #{}", index),
+            None => println!("Last active: never"),
+        }
+    
+        let stats = self.blockchain.account_stats(&address);
+        match stats.first_seen {
+            Some(index) => println!("First seen: block #{}", index),
+            None => println!("First seen: never"),
+        }
+        println!("Sent: {} transactions ({:.2})", stats.sent_count, stats.volume_sent);
+        println!("Received: {} transactions ({:.2})", stats.received_count, stats.volume_received);
+        println!("Lowest balance held: {:.2}", stats.min_balance);
+
+        let counterparties = self.blockchain.counterparties(&address);
+        if !counterparties.is_empty() {
+            println!("Counterparties:");
+            for (counterparty, count, volume) in counterparties {
+                println!("  {}: {} transactions ({:.2})", counterparty, count, volume);
             }
-            
-            println!("\nOptions:");
-            println!("1. Create new account");
-            println!("2. Select account");
-            println!("3. List all accounts");
-            println!("4. Check balance");
-            println!("5. Create transaction");
-            println!("6. View pending transactions");
-            println!("7. Create new block (validators only)");
-            println!("8. Promote account to validator");
+        }
+        Ok(())
+    }
+    
+    pub fn print_balance_proof(&self) -> Result<(), String> {
+        let address = self.get_current_user()?;
+        let proof = self.blockchain.balance_proof(&address)?;
+    
+        println!("Balance proof for {}", proof.address);
+        println!("Claimed balance: {:.2}", proof.balance);
+        println!("Proven at block #{} (balances root {})", proof.header.index, proof.header.balances_root);
+        println!("Proof steps: {}", proof.proof.len());
+        // A light client would pass headers from a node it trusts; here that is this node
+        match proof.verify(&self.blockchain.export_headers()) {
+            Ok(()) => println!("Proof verified against this node's block headers"),
+            Err(e) => println!("Proof verification FAILED: {}", e),
+        }
+        Ok(())
+    }
+    
+    pub fn print_validator_info(&self) -> Result<(), String> {
+        let address = self.get_current_user()?;
+        if !self.blockchain.is_validator(&address) {
+            return Err("Current account is not a validator".to_string());
+        }
+    
+        let blocks_produced = self.blockchain.chain.iter()
+            .skip(1)
+            .filter(|block| block.validator == address)
+            .count();
+        let (rewards, fees) = self.blockchain.validator_earnings(&address);
+    
+        println!("Validator: {}", address);
+        println!("Blocks produced: {}", blocks_produced);
+        println!("Rewards earned: {:.2}", rewards);
+        println!("Fees collected: {:.2}", fees);
+    
+        let weight = self.blockchain.validator_weight(&address);
+        let total_weight = self.blockchain.total_validator_weight();
+        let share = if total_weight > 0.0 { weight / total_weight * 100.0 } else { 0.0 };
+        println!("Voting weight: {:.2} ({:.1}% of active validators)", weight, share);
+        Ok(())
+    }
+    
+    pub fn print_inactive_validators(&self, max_idle_blocks: u32) {
+        let inactive = self.blockchain.inactive_validators(max_idle_blocks);
+        if inactive.is_empty() {
+            println!("All validators produced a block in the last {} blocks", max_idle_blocks);
+            return;
+        }
+        for validator in inactive {
+            match self.blockchain.last_produced(&validator) {
+                Some(height) => println!("{} (last block #{})", validator, height),
+                None => println!("{} (never produced)", validator),
+            }
+        }
+    }
+    
+    pub fn prune_inactive_accounts(&mut self, min_idle_blocks: u32) -> Result<usize, String> {
+        self.commit(|chain| Ok(chain.prune_inactive_accounts(min_idle_blocks)))
+    }
+
+    pub fn mine_empty_blocks(&mut self, count: usize) -> Result<(), String> {
+        if !self.dev {
+            return Err("Mining empty blocks is a developer command; restart with --dev".to_string());
+        }
+        let validator = self.get_current_user()?;
+        let mined = self.commit(|chain| chain.mine_empty_blocks(count, &validator).map_err(|e| e.to_string()))?;
+        println!("Mined {} empty blocks, height is now {}", mined.len(), self.blockchain.get_latest_block().index);
+        Ok(())
+    }
+
+    pub fn rehash_chain(&mut self) -> Result<(), String> {
+        if !self.dev {
+            return Err("Rehashing the chain is a developer command; restart with --dev".to_string());
+        }
+        self.commit(|chain| {
+            chain.rehash_chain();
+            chain
+                .validate_chain_detailed()
+                .map_err(|failure| format!("Chain is still invalid after rehashing: {}", failure))
+        })
+    }
+    
+    pub fn print_transactions_between(&self, start_ts: u64, end_ts: u64) {
+        let transactions = self.blockchain.transactions_between(start_ts, end_ts);
+        println!("Transactions between {} and {}: {}", start_ts, end_ts, transactions.len());
+        for (block_index, tx) in transactions {
+            println!("Block #{}", block_index);
+            println!("{}", tx);
+            println!("--------------------");
+        }
+    }
+    
+    pub fn print_blocks_between(&self, start_ts: u64, end_ts: u64) {
+        let blocks = self.blockchain.blocks_between(start_ts, end_ts);
+        if blocks.is_empty() {
+            println!("No blocks between {} and {}", start_ts, end_ts);
+            return;
+        }
+        println!("Blocks between {} and {}: {}", start_ts, end_ts, blocks.len());
+        for block in blocks {
+            println!(
+                "#{} at {} by {}: {} transaction(s), hash {}",
+                block.index,
+                block.timestamp,
+                block.validator,
+                block.transactions.len(),
+                block.hash
+            );
+        }
+    }
+
+    pub fn sync_from_peer(&mut self, url: &str) -> Result<bool, String> {
+        let candidate = network::fetch_chain(url)?;
+    
+        let replaced = self.blockchain.try_replace_chain(candidate)?;
+        if replaced {
+            self.save_blockchain()?;
+        }
+        Ok(replaced)
+    }
+    
+    /// Whether a menu option may be used; observers get only the ones that leave the chain alone
+    fn option_available(&self, choice: &str) -> bool {
+        !(self.observer && MUTATING_OPTIONS.contains(&choice))
+    }
+
+    pub fn run(&mut self) {
+        println!("Welcome to Private Blockchain CLI");
+        println!("--------------------------------");
+        if self.observer {
+            println!("Observer mode: options that modify the chain are disabled");
+        }
+        
+        loop {
+            if let Err(e) = self.save_event_log() {
+                println!("Error saving event log: {}", e);
+            }
+
+            // Display current status
+            if let Some(address) = &self.current_user {
+                let balance = self.blockchain.get_account_balance(address);
+                let is_validator = self.blockchain.is_validator(address);
+                println!("\nCurrent Account: {} (Balance: {:.2}) [{}]", 
+                    address, 
+                    balance,
+                    if is_validator { "Validator" } else { "User" }
+                );
+            } else {
+                println!("\nNo account selected");
+            }
+            
+            println!("\nOptions:");
+            println!("1. Create new account");
+            println!("2. Select account");
+            println!("3. List all accounts");
+            println!("4. Check balance");
+            println!("5. Create transaction");
+            println!("6. View pending transactions");
+            println!("7. Create new block (validators only)");
+            println!("8. Promote account to validator");
             println!("9. Blockchain status");
+            println!("10. Show genesis block");
+            println!("11. Import longer chain from file");
+            println!("12. Validator info");
+            println!("13. Rewrite accounts file");
+            println!("14. Account info");
+            println!("15. List all blocks");
+            println!("16. Sync chain from peer");
+            println!("17. Transfer validator role");
+            println!("18. Storage report");
+            println!("19. Clean up orphaned keypairs");
+            println!("20. Mempool summary");
+            println!("21. Save chain as");
+            println!("22. List chain participants");
+            println!("23. Show Merkle root of a block");
+            println!("24. Verify transaction in block");
+            println!("25. Export encrypted wallet");
+            println!("26. Import encrypted wallet");
+            println!("27. List inactive validators");
+            println!("28. Rehash chain (dev/migration only)");
+            println!("29. Transactions in time range");
+            println!("30. Pending balances");
+            println!("31. Diagnose chain");
+            println!("32. Prove balance");
+            println!("33. Export unsigned transaction");
+            println!("34. Sign transaction file (offline)");
+            println!("35. Submit signed transaction file");
+            println!("36. Activity over time");
+            println!("37. Merge mempool from another node's file");
+            println!("38. Reconcile accounts file with chain");
+            println!("39. Show transaction receipt");
+            println!("40. Show chain configuration");
+            println!("41. Preview next block");
+            println!("42. Pending claims");
+            println!("43. Claim held transfer");
+            println!("44. Prune inactive empty accounts");
+            println!("45. Reset chain (archives current files)");
+            println!("46. Export balance attestation");
+            println!("47. Verify balance attestation");
+            println!("48. Import block from file");
+            println!("49. Mine empty blocks (dev only)");
+            println!("50. Plan transfers (check affordability)");
+            println!("51. Benchmark chain validation");
+            println!("52. Export chain as NDJSON");
+            println!("53. Import blocks from NDJSON");
+            println!("54. Blocks in time range");
+            println!("55. Pending transactions by sender");
+            println!("56. Replay event log");
+            println!("57. Promote multiple accounts to validators");
+            println!("58. Stuck pending transactions");
+            println!("59. Export block headers (light client)");
+            println!("60. Verify header chain file");
+            println!("61. Load chain configuration from file");
             println!("0. Exit");
             
             print!("Enter your choice: ");
@@ -199,6 +1220,11 @@ impl BlockchainCLI {
             let mut choice = String::new();
             io::stdin().read_line(&mut choice).unwrap();
             
+            if !self.option_available(choice.trim()) {
+                println!("Not available in observer mode");
+                continue;
+            }
+            
             match choice.trim() {
                 "1" => {
                     let address = self.create_new_account();
@@ -233,7 +1259,9 @@ impl BlockchainCLI {
                     
                     if let Ok(index) = selection.trim().parse::<usize>() {
                         if index > 0 && index <= accounts.len() {
-                            self.select_account(&accounts[index - 1]).unwrap();
+                            if let Err(e) = self.select_account(&accounts[index - 1]) {
+                                println!("Error: {}", e);
+                            }
                         } else {
                             println!("Invalid selection");
                         }
@@ -297,7 +1325,49 @@ impl BlockchainCLI {
                         }
                     };
                     
-                    match self.create_transaction(&accounts[recipient_index], amount) {
+                    print!("Enter fee (default 0): ");
+                    io::stdout().flush().unwrap();
+                    
+                    let mut fee_input = String::new();
+                    io::stdin().read_line(&mut fee_input).unwrap();
+                    
+                    let fee = match fee_input.trim() {
+                        "" => 0.0,
+                        input => match input.parse::<f64>() {
+                            Ok(fee) if fee >= 0.0 => fee,
+                            _ => {
+                                println!("Invalid fee");
+                                continue;
+                            }
+                        },
+                    };
+                    
+                    print!("Memo (optional): ");
+                    io::stdout().flush().unwrap();
+                    let mut memo = String::new();
+                    io::stdin().read_line(&mut memo).unwrap();
+
+                    let recipient = &accounts[recipient_index];
+                    match self.preview_transaction(recipient, amount, fee, &memo) {
+                        Ok((sender_after, recipient_after)) => {
+                            println!("Your balance after: {:.2}", sender_after);
+                            println!("Recipient balance after: {:.2}", recipient_after);
+                            if self.blockchain.config().claim_threshold.is_some_and(|threshold| amount > threshold) {
+                                println!("This transfer is held until the recipient claims it");
+                            }
+                        },
+                        Err(e) => {
+                            println!("Error creating transaction: {}", e);
+                            continue;
+                        }
+                    }
+
+                    if !self.assume_yes && !confirm("Send this transaction?") {
+                        println!("Transaction cancelled");
+                        continue;
+                    }
+
+                    match self.create_transaction(recipient, amount, fee, &memo) {
                         Ok(_) => {
                             println!("Transaction created successfully");
                             self.save_blockchain().unwrap_or_else(|e| println!("Error saving: {}", e));
@@ -309,6 +1379,26 @@ impl BlockchainCLI {
                     self.print_pending_transactions();
                 },
                 "7" => {
+                    if let Some(producer) = self.blockchain.next_block_producer() {
+                        if self.current_user.as_ref() != Some(&producer) {
+                            println!("It is not your turn: the next block belongs to {}", producer);
+                            continue;
+                        }
+                    }
+                    let revenue = self.blockchain.next_block_revenue();
+                    if revenue > 0.0 {
+                        println!("Expected revenue for this block: {:.2}", revenue);
+                    }
+                    if let Some(count) = self.blockchain.small_block_size() {
+                        println!(
+                            "The block would hold only {} transaction(s) (warning threshold {})",
+                            count, self.blockchain.config().min_block_transactions_warn
+                        );
+                        if !self.assume_yes && !confirm("Create the block anyway?") {
+                            println!("Block creation cancelled");
+                            continue;
+                        }
+                    }
                     match self.create_new_block() {
                         Ok(_) => println!("Block created successfully"),
                         Err(e) => println!("Error creating block: {}", e),
@@ -353,13 +1443,969 @@ impl BlockchainCLI {
                 "9" => {
                     self.print_blockchain_status();
                 },
+                "10" => {
+                    self.print_genesis();
+                },
+                "11" => {
+                    print!("Enter blockchain file path: ");
+                    io::stdout().flush().unwrap();
+                    
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+                    
+                    match self.import_chain_from_file(path.trim()) {
+                        Ok(true) => println!("Chain replaced. New height: {}", self.blockchain.chain.len()),
+                        Ok(false) => println!("Candidate chain is not longer than the local chain"),
+                        Err(e) => println!("Error importing chain: {}", e),
+                    }
+                },
+                "12" => {
+                    self.print_validator_info().unwrap_or_else(|e| println!("Error: {}", e));
+                },
+                "13" => {
+                    match self.rewrite_accounts_file() {
+                        Ok(_) => println!("Wrote {} keypairs to {}", self.blockchain.keypairs.len(), self.account_file),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "14" => {
+                    self.print_account_info().unwrap_or_else(|e| println!("Error: {}", e));
+                },
+                "15" => {
+                    for line in self.blockchain.chain_summary() {
+                        println!("{}", line);
+                    }
+                },
+                "16" => {
+                    print!("Enter peer URL (e.g. http://localhost:9002): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut url = String::new();
+                    io::stdin().read_line(&mut url).unwrap();
+
+                    match self.sync_from_peer(url.trim()) {
+                        Ok(true) => println!("Chain replaced. New height: {}", self.blockchain.chain.len()),
+                        Ok(false) => println!("Local chain kept. Height: {}", self.blockchain.chain.len()),
+                        Err(e) => println!("Error syncing: {}", e),
+                    }
+                },
+                "17" => {
+                    if self.current_user.is_none() {
+                        println!("No account selected. Please select an account first.");
+                        continue;
+                    }
+
+                    println!("Available accounts:");
+                    let accounts = self.list_accounts();
+                    for (i, account) in accounts.iter().enumerate() {
+                        println!("{}. {}", i + 1, account);
+                    }
+
+                    print!("Select account to receive the validator role: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut selection = String::new();
+                    io::stdin().read_line(&mut selection).unwrap();
+
+                    match selection.trim().parse::<usize>() {
+                        Ok(index) if index > 0 && index <= accounts.len() => {
+                            match self.transfer_validator_role(&accounts[index - 1]) {
+                                Ok(_) => println!("Validator role transferred"),
+                                Err(e) => println!("Error: {}", e),
+                            }
+                        },
+                        _ => println!("Invalid selection"),
+                    }
+                },
+                "18" => {
+                    print!("Enter target height: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut height_input = String::new();
+                    io::stdin().read_line(&mut height_input).unwrap();
+
+                    let target_height = match height_input.trim().parse::<u32>() {
+                        Ok(height) => height,
+                        _ => {
+                            println!("Invalid height");
+                            continue;
+                        }
+                    };
+
+                    let report = self.blockchain.storage_report(target_height);
+                    println!("Current size: {} bytes", report.current_size);
+                    println!("Average per block: {:.0} bytes", report.average_block_size);
+                    println!("Projected size at height {}: {:.0} bytes", report.target_height, report.projected_size);
+                },
+                "19" => {
+                    let orphans = self.blockchain.orphaned_keypairs();
+                    if orphans.is_empty() {
+                        println!("No orphaned keypairs found");
+                        continue;
+                    }
+
+                    println!("Orphaned keypairs:");
+                    for address in &orphans {
+                        println!("  {}", address);
+                    }
+
+                    print!("Drop them? (y/n): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut answer = String::new();
+                    io::stdin().read_line(&mut answer).unwrap();
+
+                    if answer.trim().eq_ignore_ascii_case("y") {
+                        match self.drop_orphaned_keypairs() {
+                            Ok(count) => println!("Dropped {} keypairs", count),
+                            Err(e) => println!("Error: {}", e),
+                        }
+                    }
+                },
+                "20" => {
+                    let summary = self.blockchain.mempool_summary();
+                    println!("Pending transactions: {}", summary.transaction_count);
+                    println!("Total value: {:.2}", summary.total_value);
+                    println!("Total fees: {:.2}", summary.total_fees);
+                    println!("Fit in next block: {}", summary.fits_in_next_block);
+                },
+                "21" => {
+                    print!("Enter new blockchain file path: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut blockchain_path = String::new();
+                    io::stdin().read_line(&mut blockchain_path).unwrap();
+
+                    print!("Enter new accounts file path: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut accounts_path = String::new();
+                    io::stdin().read_line(&mut accounts_path).unwrap();
+
+                    print!("Switch to the new files? (y/n): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut answer = String::new();
+                    io::stdin().read_line(&mut answer).unwrap();
+                    let switch_files = answer.trim().eq_ignore_ascii_case("y");
+
+                    match self.save_as(blockchain_path.trim(), accounts_path.trim(), switch_files) {
+                        Ok(_) => println!("Saved chain to {} and {}", blockchain_path.trim(), accounts_path.trim()),
+                        Err(e) => println!("Error saving: {}", e),
+                    }
+                },
+                "22" => {
+                    let mut participants: Vec<String> = self.blockchain.participants().into_iter().collect();
+                    participants.sort();
+                    println!("Participants: {}", participants.len());
+                    for address in participants {
+                        println!("  {}", address);
+                    }
+                },
+                "23" => {
+                    print!("Enter block index: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut index_input = String::new();
+                    io::stdin().read_line(&mut index_input).unwrap();
+
+                    match index_input.trim().parse::<usize>().ok().and_then(|index| self.blockchain.chain.get(index)) {
+                        Some(block) => {
+                            println!("Merkle root of block #{}: {}", block.index, block.merkle_root());
+                            for tx in &block.transactions {
+                                println!("  tx {}", tx.calculate_hash());
+                            }
+                        },
+                        None => println!("Invalid block index"),
+                    }
+                },
+                "24" => {
+                    print!("Enter transaction hash: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut tx_hash = String::new();
+                    io::stdin().read_line(&mut tx_hash).unwrap();
+
+                    print!("Enter block index: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut index_input = String::new();
+                    io::stdin().read_line(&mut index_input).unwrap();
+
+                    let block_index = match index_input.trim().parse::<u32>() {
+                        Ok(index) => index,
+                        _ => {
+                            println!("Invalid block index");
+                            continue;
+                        }
+                    };
+
+                    match self.blockchain.verify_transaction_inclusion(block_index, tx_hash.trim()) {
+                        Ok(true) => println!("Transaction is included in block #{}", block_index),
+                        Ok(false) => println!("Transaction is NOT included in block #{}", block_index),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "25" => {
+                    print!("Enter wallet file path: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    print!("Enter passphrase: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut passphrase = String::new();
+                    io::stdin().read_line(&mut passphrase).unwrap();
+
+                    match self.export_wallet(path.trim(), passphrase.trim_end_matches(['\r', '\n'])) {
+                        Ok(count) => println!("Exported {} keypairs to {}", count, path.trim()),
+                        Err(e) => println!("Error exporting wallet: {}", e),
+                    }
+                },
+                "26" => {
+                    print!("Enter wallet file path: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    print!("Enter passphrase: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut passphrase = String::new();
+                    io::stdin().read_line(&mut passphrase).unwrap();
+
+                    match self.import_wallet(path.trim(), passphrase.trim_end_matches(['\r', '\n'])) {
+                        Ok(count) => println!("Imported {} keypairs", count),
+                        Err(e) => println!("Error importing wallet: {}", e),
+                    }
+                },
+                "27" => {
+                    print!("Maximum idle blocks: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut window_input = String::new();
+                    io::stdin().read_line(&mut window_input).unwrap();
+
+                    match window_input.trim().parse::<u32>() {
+                        Ok(window) => self.print_inactive_validators(window),
+                        Err(_) => println!("Invalid number"),
+                    }
+                },
+                "28" => {
+                    println!("This rewrites every block hash. Use it only after a format migration on a dev chain.");
+                    print!("Type REHASH to continue: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut confirm_input = String::new();
+                    io::stdin().read_line(&mut confirm_input).unwrap();
+                    if confirm_input.trim() != "REHASH" {
+                        println!("Rehash cancelled");
+                        continue;
+                    }
+
+                    match self.rehash_chain() {
+                        Ok(_) => println!("Chain rehashed. Latest hash: {}", self.blockchain.get_latest_block().hash),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "29" => {
+                    print!("Start timestamp (unix seconds): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut start_input = String::new();
+                    io::stdin().read_line(&mut start_input).unwrap();
+
+                    print!("End timestamp (unix seconds): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut end_input = String::new();
+                    io::stdin().read_line(&mut end_input).unwrap();
+
+                    match (start_input.trim().parse::<u64>(), end_input.trim().parse::<u64>()) {
+                        (Ok(start_ts), Ok(end_ts)) if start_ts <= end_ts => {
+                            self.print_transactions_between(start_ts, end_ts)
+                        },
+                        _ => println!("Invalid time range"),
+                    }
+                },
+                "30" => {
+                    self.print_pending_balances();
+                },
+                "31" => {
+                    self.print_diagnosis();
+                },
+                "32" => {
+                    self.print_balance_proof().unwrap_or_else(|e| println!("Error: {}", e));
+                },
+                "33" => {
+                    print!("Sender address: ");
+                    io::stdout().flush().unwrap();
+                    let mut sender = String::new();
+                    io::stdin().read_line(&mut sender).unwrap();
+
+                    print!("Recipient address: ");
+                    io::stdout().flush().unwrap();
+                    let mut recipient = String::new();
+                    io::stdin().read_line(&mut recipient).unwrap();
+
+                    print!("Enter amount: ");
+                    io::stdout().flush().unwrap();
+                    let mut amount_input = String::new();
+                    io::stdin().read_line(&mut amount_input).unwrap();
+                    let amount = match amount_input.trim().parse::<f64>() {
+                        Ok(amt) if amt > 0.0 => amt,
+                        _ => {
+                            println!("Invalid amount");
+                            continue;
+                        }
+                    };
+
+                    print!("Enter fee (default 0): ");
+                    io::stdout().flush().unwrap();
+                    let mut fee_input = String::new();
+                    io::stdin().read_line(&mut fee_input).unwrap();
+                    let fee = match fee_input.trim() {
+                        "" => 0.0,
+                        input => match input.parse::<f64>() {
+                            Ok(fee) if fee >= 0.0 => fee,
+                            _ => {
+                                println!("Invalid fee");
+                                continue;
+                            }
+                        },
+                    };
+
+                    print!("Memo (optional): ");
+                    io::stdout().flush().unwrap();
+                    let mut memo = String::new();
+                    io::stdin().read_line(&mut memo).unwrap();
+
+                    print!("Output file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.export_unsigned_transaction(sender.trim(), recipient.trim(), amount, fee, &memo, path.trim()) {
+                        Ok(_) => println!("Unsigned transaction written to {}", path.trim()),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "34" => {
+                    print!("Transaction file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.sign_transaction_file(path.trim()) {
+                        Ok(_) => println!("Signed transaction written to {}", path.trim()),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "35" => {
+                    print!("Signed transaction file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.submit_from_file(path.trim()) {
+                        Ok(_) => println!("Transaction submitted"),
+                        Err(e) => println!("Error submitting transaction: {}", e),
+                    }
+                },
+                "36" => {
+                    print!("Bucket size in seconds (default 3600): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut bucket_input = String::new();
+                    io::stdin().read_line(&mut bucket_input).unwrap();
+
+                    match bucket_input.trim() {
+                        "" => self.print_activity(3600),
+                        input => match input.parse::<u64>() {
+                            Ok(seconds) if seconds > 0 => self.print_activity(seconds),
+                            _ => println!("Invalid bucket size"),
+                        },
+                    }
+                },
+                "37" => {
+                    print!("Other node's blockchain file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.merge_mempool_from_file(path.trim()) {
+                        Ok((admitted, offered)) => println!("Admitted {} of {} pending transactions", admitted, offered),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "38" => {
+                    self.print_reconciliation();
+                },
+                "39" => {
+                    print!("Transaction hash: ");
+                    io::stdout().flush().unwrap();
+                    let mut tx_hash = String::new();
+                    io::stdin().read_line(&mut tx_hash).unwrap();
+
+                    match self.blockchain.receipt(tx_hash.trim()) {
+                        Some(receipt) => println!("{}", receipt),
+                        None => println!("Transaction {} is not confirmed", tx_hash.trim()),
+                    }
+                },
+                "40" => {
+                    println!("Chain Configuration");
+                    println!("-------------------");
+                    println!("{}", self.blockchain.config());
+                },
+                "41" => {
+                    self.print_block_preview().unwrap_or_else(|e| println!("Error: {}", e));
+                },
+                "42" => {
+                    self.print_pending_claims();
+                },
+                "43" => {
+                    print!("Held transfer hash: ");
+                    io::stdout().flush().unwrap();
+                    let mut tx_hash = String::new();
+                    io::stdin().read_line(&mut tx_hash).unwrap();
+
+                    print!("Fee (default 0): ");
+                    io::stdout().flush().unwrap();
+                    let mut fee_input = String::new();
+                    io::stdin().read_line(&mut fee_input).unwrap();
+                    let fee = match fee_input.trim() {
+                        "" => 0.0,
+                        input => match input.parse::<f64>() {
+                            Ok(fee) => fee,
+                            Err(_) => {
+                                println!("Invalid fee");
+                                continue;
+                            }
+                        },
+                    };
+
+                    match self.claim_transfer(tx_hash.trim(), fee) {
+                        Ok(_) => println!("Claim queued; the transfer is credited once it is mined"),
+                        Err(e) => println!("Error claiming transfer: {}", e),
+                    }
+                },
+                "44" => {
+                    print!("Minimum idle blocks: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut window_input = String::new();
+                    io::stdin().read_line(&mut window_input).unwrap();
+
+                    match window_input.trim().parse::<u32>() {
+                        Ok(window) => match self.prune_inactive_accounts(window) {
+                            Ok(pruned) => println!("Pruned {} inactive zero-balance account(s)", pruned),
+                            Err(e) => println!("Error: {}", e),
+                        },
+                        Err(_) => println!("Invalid number"),
+                    }
+                },
+                "45" => {
+                    println!("This archives {} and {} and starts a brand-new chain.", self.blockchain_file, self.account_file);
+                    let reuse_admin = self.current_user.is_some()
+                        && confirm("Keep the current account as the admin of the new chain?");
+                    print!("Type {} to continue: ", RESET_CONFIRMATION);
+                    io::stdout().flush().unwrap();
+
+                    let mut confirm_input = String::new();
+                    io::stdin().read_line(&mut confirm_input).unwrap();
+                    match self.reset_chain(confirm_input.trim(), reuse_admin) {
+                        Ok(archived) => {
+                            for path in archived {
+                                println!("Archived to {}", path);
+                            }
+                            println!("Started a new chain");
+                        },
+                        Err(e) => println!("Error resetting chain: {}", e),
+                    }
+                },
+                "46" => {
+                    print!("Output file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.export_balance_attestation(path.trim()) {
+                        Ok(_) => println!("Attestation written to {}", path.trim()),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "47" => {
+                    print!("Attestation file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.verify_balance_attestation(path.trim()) {
+                        Ok(attestation) => println!(
+                            "Verified: {} acknowledges {:.2} after {} sent transactions (block #{})",
+                            attestation.address, attestation.balance, attestation.nonce, attestation.block_index
+                        ),
+                        Err(e) => println!("Attestation rejected: {}", e),
+                    }
+                },
+                "48" => {
+                    print!("Block file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.import_block_from_file(path.trim()) {
+                        Ok(index) => println!("Appended block #{}", index),
+                        Err(e) => println!("Error importing block: {}", e),
+                    }
+                },
+                "49" => {
+                    print!("Number of blocks: ");
+                    io::stdout().flush().unwrap();
+                    let mut count_input = String::new();
+                    io::stdin().read_line(&mut count_input).unwrap();
+
+                    match count_input.trim().parse::<usize>() {
+                        Ok(count) => self.mine_empty_blocks(count).unwrap_or_else(|e| println!("Error: {}", e)),
+                        Err(_) => println!("Invalid number"),
+                    }
+                },
+                "50" => {
+                    let accounts = self.list_accounts();
+                    for (i, account) in accounts.iter().enumerate() {
+                        println!("{}. {}", i + 1, account);
+                    }
+                    println!("Enter one transfer per line as: recipient-number amount [fee]");
+                    println!("Finish with an empty line.");
+
+                    let mut transfers = Vec::new();
+                    loop {
+                        print!("> ");
+                        io::stdout().flush().unwrap();
+                        let mut line = String::new();
+                        if io::stdin().read_line(&mut line).unwrap() == 0 || line.trim().is_empty() {
+                            break;
+                        }
+
+                        let parts: Vec<&str> = line.split_whitespace().collect();
+                        let recipient = parts
+                            .first()
+                            .and_then(|number| number.parse::<usize>().ok())
+                            .filter(|number| *number > 0 && *number <= accounts.len())
+                            .map(|number| accounts[number - 1].clone());
+                        let amount = parts.get(1).and_then(|amount| amount.parse::<f64>().ok());
+                        let fee = match parts.get(2) {
+                            Some(fee) => fee.parse::<f64>().ok(),
+                            None => Some(0.0),
+                        };
+                        match (recipient, amount, fee) {
+                            (Some(recipient), Some(amount), Some(fee)) if parts.len() <= 3 => {
+                                transfers.push((recipient, amount, fee))
+                            },
+                            _ => println!("Invalid line, expected: recipient-number amount [fee]"),
+                        }
+                    }
+
+                    match self.plan_transfers(&transfers) {
+                        Ok((required, available)) => {
+                            println!("Transfers: {}", transfers.len());
+                            println!("Required (amounts plus fees): {:.2}", required);
+                            println!("Available to spend: {:.2}", available);
+                            if required <= available {
+                                println!("The account can afford all of them");
+                            } else {
+                                println!("Short by {:.2}", required - available);
+                            }
+                        },
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "51" => {
+                    self.benchmark_validation();
+                },
+                "52" => {
+                    print!("Output file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.export_ndjson(path.trim()) {
+                        Ok(count) => println!("Exported {} blocks to {}", count, path.trim()),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "53" => {
+                    print!("NDJSON file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.import_ndjson(path.trim()) {
+                        Ok(count) => println!("Appended {} blocks", count),
+                        Err(e) => println!("Error importing blocks: {}", e),
+                    }
+                },
+                "54" => {
+                    print!("Start timestamp (unix seconds): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut start_input = String::new();
+                    io::stdin().read_line(&mut start_input).unwrap();
+
+                    print!("End timestamp (unix seconds): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut end_input = String::new();
+                    io::stdin().read_line(&mut end_input).unwrap();
+
+                    match (start_input.trim().parse::<u64>(), end_input.trim().parse::<u64>()) {
+                        (Ok(start_ts), Ok(end_ts)) if start_ts <= end_ts => {
+                            self.print_blocks_between(start_ts, end_ts)
+                        },
+                        _ => println!("Invalid time range"),
+                    }
+                },
+                "55" => {
+                    self.print_pending_by_sender();
+                },
+                "56" => {
+                    print!("Event log file: ");
+                    io::stdout().flush().unwrap();
+
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    if let Err(e) = self.replay_event_log(path.trim()) {
+                        println!("Error: {}", e);
+                    }
+                },
+                "57" => {
+                    if self.current_user.is_none() {
+                        println!("No account selected. Please select an account first.");
+                        continue;
+                    }
+
+                    println!("Available accounts:");
+                    let accounts = self.list_accounts();
+                    for (i, account) in accounts.iter().enumerate() {
+                        let is_validator = self.blockchain.is_validator(account);
+                        println!("{}. {} [{}]",
+                            i + 1,
+                            account,
+                            if is_validator { "Already Validator" } else { "User" }
+                        );
+                    }
+
+                    print!("Accounts to promote (numbers or addresses, separated by spaces or commas): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut selection = String::new();
+                    io::stdin().read_line(&mut selection).unwrap();
+
+                    // Numbers pick from the list above; anything else is taken as an address
+                    let addresses: Vec<String> = selection
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|entry| !entry.is_empty())
+                        .map(|entry| match entry.parse::<usize>() {
+                            Ok(index) if index > 0 && index <= accounts.len() => accounts[index - 1].clone(),
+                            _ => entry.to_string(),
+                        })
+                        .collect();
+                    if addresses.is_empty() {
+                        println!("No accounts given");
+                        continue;
+                    }
+
+                    let atomic = confirm("Promote none of them if any one fails?");
+                    match self.promote_validators(&addresses, atomic) {
+                        Ok(results) => {
+                            let failed = results.iter().filter(|result| result.is_err()).count();
+                            let rolled_back = atomic && failed > 0;
+                            for (address, result) in addresses.iter().zip(results) {
+                                match result {
+                                    Ok(()) if rolled_back => println!("{}: skipped", address),
+                                    Ok(()) => println!("{}: promoted", address),
+                                    Err(e) => println!("{}: {}", address, e),
+                                }
+                            }
+                            let promoted = if rolled_back { 0 } else { addresses.len() - failed };
+                            println!("Promoted {} of {} account(s)", promoted, addresses.len());
+                        },
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "58" => {
+                    print!("Blocks to wait (default 3): ");
+                    io::stdout().flush().unwrap();
+
+                    let mut input = String::new();
+                    io::stdin().read_line(&mut input).unwrap();
+
+                    let input = input.trim();
+                    match if input.is_empty() { Ok(3) } else { input.parse::<u32>() } {
+                        Ok(blocks) => self.print_stuck_transactions(blocks),
+                        Err(_) => println!("Invalid number of blocks"),
+                    }
+                },
+                "59" => {
+                    print!("Output file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.export_headers(path.trim()) {
+                        Ok(count) => println!("Wrote {} headers to {}", count, path.trim()),
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
+                "60" => {
+                    print!("Header file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    if let Err(e) = self.verify_header_file(path.trim()) {
+                        println!("Header chain rejected: {}", e);
+                    }
+                },
+                "61" => {
+                    print!("Config file: ");
+                    io::stdout().flush().unwrap();
+                    let mut path = String::new();
+                    io::stdin().read_line(&mut path).unwrap();
+
+                    match self.load_config(path.trim()) {
+                        Ok(()) => {
+                            println!("Chain configuration updated");
+                            println!("{}", self.blockchain.config());
+                        },
+                        Err(e) => println!("Error: {}", e),
+                    }
+                },
                 "0" => {
                     println!("Exiting...");
-                    self.save_blockchain().unwrap_or_else(|e| println!("Error saving: {}", e));
+                    if !self.observer {
+                        self.save_blockchain().unwrap_or_else(|e| println!("Error saving: {}", e));
+                    }
+                    if let Err(e) = self.save_event_log() {
+                        println!("Error saving event log: {}", e);
+                    }
                     break;
                 },
                 _ => println!("Invalid choice"),
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn cli_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("blockchain-cli-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn path_in(dir: &Path, name: &str) -> String {
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    fn open_cli(dir: &Path, options: CliOptions) -> Result<BlockchainCLI, String> {
+        BlockchainCLI::new(&path_in(dir, "chain.json"), &path_in(dir, "accounts.json"), options)
+    }
+
+    #[test]
+    fn save_as_writes_loadable_copies_and_optionally_switches() {
+        let dir = cli_dir("save-as");
+        let mut cli = open_cli(&dir, CliOptions::default()).unwrap();
+        let (copy_chain, copy_accounts) = (path_in(&dir, "copy-chain.json"), path_in(&dir, "copy-accounts.json"));
+
+        cli.save_as(&copy_chain, &copy_accounts, false).unwrap();
+        let copy = Blockchain::load_from_file(&copy_chain, &copy_accounts, None).unwrap();
+        assert_eq!(copy.fingerprint(), cli.blockchain.fingerprint());
+        assert_eq!(copy.keypairs.len(), cli.blockchain.keypairs.len());
+        assert_eq!(cli.blockchain_file, path_in(&dir, "chain.json"));
+
+        cli.save_as(&copy_chain, &copy_accounts, true).unwrap();
+        assert_eq!((cli.blockchain_file.as_str(), cli.account_file.as_str()), (copy_chain.as_str(), copy_accounts.as_str()));
+        assert!(Path::new(&format!("{}.lock", copy_chain)).exists());
+        assert!(!Path::new(&path_in(&dir, "chain.json.lock")).exists());
+
+        let missing = path_in(&dir.join("missing"), "chain.json");
+        assert!(cli.save_as(&missing, &copy_accounts, false).is_err());
+        assert!(!Path::new(&missing).exists());
+    }
+
+    #[test]
+    fn observers_read_a_lone_chain_file_but_cannot_change_it() {
+        let dir = cli_dir("observer");
+        let keypair = Keypair::generate(&mut OsRng);
+        let validator = keypair.verifier().address();
+        let mut blockchain = Blockchain::new(&validator);
+        blockchain.register_keypair(keypair);
+        blockchain.add_validator(validator.clone()).unwrap();
+        blockchain.save_to_file(&path_in(&dir, "chain.json"), &path_in(&dir, "accounts.json")).unwrap();
+        fs::remove_file(path_in(&dir, "accounts.json")).unwrap();
+
+        let mut cli = open_cli(&dir, CliOptions { observer: true, ..CliOptions::default() }).unwrap();
+        assert!(MUTATING_OPTIONS.iter().all(|option| !cli.option_available(option)));
+        assert!(["2", "3", "4", "9"].iter().all(|option| cli.option_available(option)));
+        assert!(!Path::new(&path_in(&dir, "chain.json.lock")).exists());
+
+        assert_eq!(cli.list_accounts(), vec![validator.clone()]);
+        cli.select_account(&validator).unwrap();
+        assert_eq!(cli.blockchain.get_account_balance(&validator), 1000.0);
+        assert!(cli.blockchain.validate_chain());
+        assert!(cli.save_blockchain().is_err());
+        assert!(cli.commit(|_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn a_chain_without_its_accounts_file_loads_read_only() {
+        let dir = cli_dir("missing-accounts");
+        let blockchain = Blockchain::new("genesis");
+        blockchain.save_to_file(&path_in(&dir, "chain.json"), &path_in(&dir, "accounts.json")).unwrap();
+        fs::remove_file(path_in(&dir, "accounts.json")).unwrap();
+
+        let cli = open_cli(&dir, CliOptions::default()).unwrap();
+        assert!(cli.observer);
+        assert_eq!(cli.blockchain.fingerprint(), blockchain.fingerprint());
+        assert!(cli.save_blockchain().is_err());
+    }
+
+    #[test]
+    fn a_corrupt_chain_file_is_left_alone() {
+        let dir = cli_dir("corrupt-chain");
+        let chain_file = path_in(&dir, "chain.json");
+        fs::write(&chain_file, "{ not a chain").unwrap();
+
+        assert!(open_cli(&dir, CliOptions::default()).is_err());
+        assert_eq!(fs::read_to_string(&chain_file).unwrap(), "{ not a chain");
+        assert!(!Path::new(&path_in(&dir, "chain.json.lock")).exists());
+    }
+
+    #[test]
+    fn rehashing_repairs_stale_hashes_on_dev_chains_only() {
+        let dir = cli_dir("rehash");
+        let mut cli = open_cli(&dir, CliOptions::default()).unwrap();
+        let admin = cli.list_accounts().remove(0);
+        for _ in 0..2 {
+            let latest = cli.blockchain.get_latest_block();
+            let mut block = Block::new(latest.index + 1, Vec::new(), latest.hash.clone(), admin.clone());
+            block.sign(cli.blockchain.keypairs[&admin].as_ref()).unwrap();
+            cli.blockchain.append_block(block).unwrap();
+        }
+        // Stands in for a block hashed under an older format
+        cli.blockchain.chain[1].timestamp -= 1;
+        assert!(!cli.blockchain.validate_chain());
+
+        assert!(cli.rehash_chain().is_err());
+        assert!(!cli.blockchain.validate_chain());
+
+        cli.dev = true;
+        cli.rehash_chain().unwrap();
+        assert!(cli.blockchain.validate_chain());
+        let saved = Blockchain::load_read_only(&path_in(&dir, "chain.json")).unwrap();
+        assert!(saved.validate_chain());
+        assert_eq!(saved.fingerprint(), cli.blockchain.fingerprint());
+    }
+
+    #[test]
+    fn offline_signed_transactions_are_submitted_unless_tampered() {
+        let dir = cli_dir("offline");
+        let mut cli = open_cli(&dir, CliOptions::default()).unwrap();
+        let admin = cli.list_accounts().remove(0);
+        let recipient = cli.create_new_account();
+
+        let signed = path_in(&dir, "signed.json");
+        cli.export_unsigned_transaction(&admin, &recipient, 25.0, 0.0, "rent", &signed).unwrap();
+        cli.sign_transaction_file(&signed).unwrap();
+        let tampered = path_in(&dir, "tampered.json");
+        let mut transaction = read_transaction_file(&signed).unwrap();
+        transaction.amount = 250.0;
+        fs::write(&tampered, serde_json::to_string(&transaction).unwrap()).unwrap();
+
+        assert!(cli.submit_from_file(&tampered).is_err());
+        assert!(cli.blockchain.pending_transactions.is_empty());
+        cli.submit_from_file(&signed).unwrap();
+        assert_eq!(cli.blockchain.pending_transactions.len(), 1);
+        assert_eq!(cli.blockchain.pending_transactions[0].amount, 25.0);
+    }
+
+
+    #[test]
+    fn reset_requires_the_typed_confirmation() {
+        let dir = cli_dir("reset");
+        let mut cli = open_cli(&dir, CliOptions::default()).unwrap();
+        let admin = cli.list_accounts().remove(0);
+        let latest = cli.blockchain.get_latest_block();
+        let mut block = Block::new(latest.index + 1, Vec::new(), latest.hash.clone(), admin.clone());
+        block.sign(cli.blockchain.keypairs[&admin].as_ref()).unwrap();
+        cli.blockchain.append_block(block).unwrap();
+        cli.save_blockchain().unwrap();
+        let saved = fs::read_to_string(path_in(&dir, "chain.json")).unwrap();
+
+        for confirmation in ["", "reset", "yes"] {
+            assert!(cli.reset_chain(confirmation, false).is_err());
+        }
+        assert_eq!(cli.blockchain.chain.len(), 2);
+        assert_eq!(fs::read_to_string(path_in(&dir, "chain.json")).unwrap(), saved);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+
+        let archived = cli.reset_chain("RESET", false).unwrap();
+        assert_eq!(archived.len(), 2);
+        assert_eq!(fs::read_to_string(&archived[0]).unwrap(), saved);
+        assert_eq!(cli.blockchain.chain.len(), 1);
+        let fresh = Blockchain::load_read_only(&path_in(&dir, "chain.json")).unwrap();
+        assert_eq!(fresh.chain.len(), 1);
+        assert_eq!(fresh.fingerprint(), cli.blockchain.fingerprint());
+    }
+
+
+    #[test]
+    fn planned_transfers_are_checked_against_the_spendable_balance() {
+        let dir = cli_dir("plan");
+        let mut cli = open_cli(&dir, CliOptions::default()).unwrap();
+        let admin = cli.list_accounts().remove(0);
+        assert!(cli.plan_transfers(&[]).is_err());
+        cli.select_account(&admin).unwrap();
+
+        let mut transfers = vec![("alice".to_string(), 300.0, 1.0), ("bob".to_string(), 600.0, 2.5)];
+        let (required, available) = cli.plan_transfers(&transfers).unwrap();
+        assert_eq!((required, available), (903.5, 1000.0));
+        assert!(required <= available);
+
+        transfers.push(("carol".to_string(), 100.0, 0.0));
+        let (required, available) = cli.plan_transfers(&transfers).unwrap();
+        assert_eq!(required, 1003.5);
+        assert!(required > available);
+    }
+
+
+    #[test]
+    fn timed_validation_reports_nonzero_throughput() {
+        let dir = cli_dir("benchmark");
+        let mut cli = open_cli(&dir, CliOptions::default()).unwrap();
+        let admin = cli.list_accounts().remove(0);
+        for _ in 0..3 {
+            let latest = cli.blockchain.get_latest_block();
+            let mut block = Block::new(latest.index + 1, Vec::new(), latest.hash.clone(), admin.clone());
+            block.sign(cli.blockchain.keypairs[&admin].as_ref()).unwrap();
+            cli.blockchain.append_block(block).unwrap();
+        }
+
+        let benchmark = cli.time_validation();
+        assert_eq!(benchmark.result, Ok(()));
+        assert_eq!(benchmark.blocks, 4);
+        assert_eq!(benchmark.transactions, cli.blockchain.chain[0].transactions.len());
+        let (blocks_per_second, transactions_per_second) = benchmark.throughput();
+        assert!(blocks_per_second > 0.0 && blocks_per_second.is_finite());
+        assert!(transactions_per_second > 0.0 && transactions_per_second.is_finite());
+    }
+}
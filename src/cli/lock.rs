@@ -0,0 +1,145 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Exclusive lock on a node's data files, released when dropped
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Acquires the lock next to the blockchain file, refusing if another instance holds it
+    pub fn acquire(blockchain_file: &str, force: bool) -> Result<Self, String> {
+        let path = PathBuf::from(format!("{}.lock", blockchain_file));
+
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                // Record the owning process to help diagnose stale locks
+                write!(file, "{}", process::id())
+                    .map_err(|e| format!("Failed to write lock file: {}", e))?;
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let owner = fs::read_to_string(&path).unwrap_or_default();
+                if force && owner_is_running(owner.trim()) {
+                    return Err(format!(
+                        "Another instance (pid {}) is still running on {}; --force only overrides stale locks.",
+                        owner.trim(),
+                        blockchain_file
+                    ));
+                }
+                if !force {
+                    return Err(format!(
+                        "Another instance (pid {}) is using {}. Remove {} or use --force to override.",
+                        owner.trim(),
+                        blockchain_file,
+                        path.display()
+                    ));
+                }
+                println!(
+                    "Warning: overriding stale lock held by pid {} on {}",
+                    owner.trim(),
+                    blockchain_file
+                );
+                fs::write(&path, process::id().to_string())
+                    .map_err(|e| format!("Failed to write lock file: {}", e))?;
+            }
+            Err(e) => return Err(format!("Failed to create lock file: {}", e)),
+        }
+
+        Ok(FileLock { path })
+    }
+}
+
+/// Whether the process recorded in a lock file still exists
+fn owner_is_running(owner: &str) -> bool {
+    match owner.parse::<u32>() {
+        Ok(pid) => process_exists(pid),
+        // An empty or garbled lock file has no owner to protect
+        Err(_) => false,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn process_exists(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_exists(pid: u32) -> bool {
+    // Signal 0 is never delivered; kill only reports whether the process exists.
+    // If kill itself cannot run, assume the owner is alive rather than race it
+    process::Command::new("kill")
+        .arg("-0")
+        .arg(pid.to_string())
+        .stderr(process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+#[cfg(not(unix))]
+fn process_exists(_pid: u32) -> bool {
+    // No liveness check is available here, so --force is taken at its word
+    false
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // A forced takeover by another instance leaves its own pid here; that lock is not ours to remove
+        let ours = fs::read_to_string(&self.path)
+            .map(|owner| owner.trim() == process::id().to_string())
+            .unwrap_or(false);
+        if ours {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn chain_path(name: &str) -> String {
+        let dir = env::temp_dir().join(format!("blockchain-lock-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("blockchain.json").to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn force_refuses_a_live_owner() {
+        let chain = chain_path("live");
+        // The test process itself is certainly running
+        fs::write(format!("{}.lock", chain), process::id().to_string()).unwrap();
+
+        assert!(FileLock::acquire(&chain, true).is_err());
+        assert!(FileLock::acquire(&chain, false).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn force_takes_over_a_stale_lock() {
+        let chain = chain_path("stale");
+        let lock_path = format!("{}.lock", chain);
+        fs::write(&lock_path, u32::MAX.to_string()).unwrap();
+
+        assert!(FileLock::acquire(&chain, false).is_err());
+        let lock = FileLock::acquire(&chain, true).unwrap();
+        assert_eq!(fs::read_to_string(&lock_path).unwrap(), process::id().to_string());
+        drop(lock);
+        assert!(!Path::new(&lock_path).exists());
+    }
+
+    #[test]
+    fn drop_leaves_a_lock_taken_over_by_another_instance() {
+        let chain = chain_path("takeover");
+        let lock_path = format!("{}.lock", chain);
+        let _ = fs::remove_file(&lock_path);
+
+        let lock = FileLock::acquire(&chain, false).unwrap();
+        fs::write(&lock_path, "4242").unwrap();
+        drop(lock);
+        assert_eq!(fs::read_to_string(&lock_path).unwrap(), "4242");
+    }
+}
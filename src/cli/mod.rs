@@ -1 +1,5 @@
-pub mod blockchain_cli;
\ No newline at end of file
+pub mod args;
+pub mod blockchain_cli;
+pub mod lock;
+pub mod profiles;
+pub mod watch;
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Index of named chain profiles, kept in the working directory
+pub const PROFILES_FILE: &str = "profiles.json";
+
+/// The data files a named profile points at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub blockchain_file: String,
+    pub accounts_file: String,
+}
+
+/// Reads every profile, returning an empty index when none have been saved yet
+pub fn load_profiles() -> Result<BTreeMap<String, Profile>, String> {
+    load_index(PROFILES_FILE)
+}
+
+/// Adds or replaces a profile in the index
+pub fn save_profile(name: &str, profile: Profile) -> Result<(), String> {
+    save_to_index(PROFILES_FILE, name, profile)
+}
+
+/// Looks up the files for a named profile
+pub fn resolve(name: &str) -> Result<Profile, String> {
+    resolve_in(PROFILES_FILE, name)
+}
+
+fn load_index(index: &str) -> Result<BTreeMap<String, Profile>, String> {
+    if !Path::new(index).exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = fs::read_to_string(index)
+        .map_err(|e| format!("Failed to read {}: {}", index, e))?;
+    serde_json::from_str(&data).map_err(|e| format!("Failed to parse {}: {}", index, e))
+}
+
+fn save_to_index(index: &str, name: &str, profile: Profile) -> Result<(), String> {
+    let mut profiles = load_index(index)?;
+    profiles.insert(name.to_string(), profile);
+    let data = serde_json::to_string_pretty(&profiles)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    fs::write(index, data).map_err(|e| format!("Failed to write {}: {}", index, e))
+}
+
+fn resolve_in(index: &str, name: &str) -> Result<Profile, String> {
+    load_index(index)?
+        .remove(name)
+        .ok_or_else(|| format!("Unknown profile '{}'. Use --list-profiles to see saved profiles.", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Blockchain;
+    use std::path::PathBuf;
+
+    fn profile_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("blockchain-profiles-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn each_profile_resolves_to_its_own_chain() {
+        let dir = profile_dir();
+        let index = dir.join("profiles.json").to_string_lossy().into_owned();
+        let mut fingerprints = Vec::new();
+        for name in ["devnet", "testnet"] {
+            let profile = Profile {
+                blockchain_file: dir.join(format!("{}-chain.json", name)).to_string_lossy().into_owned(),
+                accounts_file: dir.join(format!("{}-accounts.json", name)).to_string_lossy().into_owned(),
+            };
+            let blockchain = Blockchain::new(name);
+            blockchain.save_to_file(&profile.blockchain_file, &profile.accounts_file).unwrap();
+            fingerprints.push(blockchain.fingerprint());
+            save_to_index(&index, name, profile).unwrap();
+        }
+
+        assert_eq!(load_index(&index).unwrap().len(), 2);
+        for (name, fingerprint) in ["devnet", "testnet"].iter().zip(&fingerprints) {
+            let profile = resolve_in(&index, name).unwrap();
+            let loaded = Blockchain::load_from_file(&profile.blockchain_file, &profile.accounts_file, None).unwrap();
+            assert_eq!(&loaded.fingerprint(), fingerprint);
+            assert!(loaded.accounts.contains_key(*name));
+        }
+        assert!(resolve_in(&index, "mainnet").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::core::Blockchain;
+use crate::models::Block;
+use std::thread;
+use std::time::Duration;
+
+/// How often the blockchain file is re-read in watch mode
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+fn print_block(block: &Block) {
+    println!("{}", block);
+    for tx in &block.transactions {
+        println!("    {} -> {}: {:.2} (fee {:.2})", tx.sender, tx.recipient, tx.amount, tx.fee);
+    }
+}
+
+/// Blocks found by one poll of the blockchain file
+struct NewBlocks {
+    /// Height from which blocks reported earlier were replaced, if any were
+    replaced_from: Option<usize>,
+    blocks: Vec<Block>,
+}
+
+/// Follows a blockchain file, remembering which blocks have already been reported
+struct BlockWatcher {
+    blockchain_file: String,
+    seen: Vec<String>,
+}
+
+impl BlockWatcher {
+    /// Starts after the blocks currently in the file
+    fn new(blockchain_file: &str) -> Result<Self, String> {
+        let chain = Blockchain::load_read_only(blockchain_file)?.chain;
+        Ok(BlockWatcher {
+            blockchain_file: blockchain_file.to_string(),
+            seen: chain.iter().map(|block| block.hash.clone()).collect(),
+        })
+    }
+
+    /// Re-reads the file once, returning the blocks that were not in it at the last poll
+    fn poll(&mut self) -> Result<NewBlocks, String> {
+        let chain = Blockchain::load_read_only(&self.blockchain_file)?.chain;
+
+        let common = self
+            .seen
+            .iter()
+            .zip(chain.iter())
+            .take_while(|(hash, block)| **hash == block.hash)
+            .count();
+        let replaced_from = if common < self.seen.len() { Some(common) } else { None };
+
+        self.seen = chain.iter().map(|block| block.hash.clone()).collect();
+        Ok(NewBlocks { replaced_from, blocks: chain.into_iter().skip(common).collect() })
+    }
+}
+
+/// Prints every new block written to the blockchain file until the process is interrupted
+///
+/// Reads without locking, so it can follow a file another node instance is writing.
+pub fn watch_blocks(blockchain_file: &str, poll_interval: Duration) -> Result<(), String> {
+    let mut watcher = BlockWatcher::new(blockchain_file)?;
+    println!("Watching {} from block #{} (Ctrl+C to stop)", blockchain_file, watcher.seen.len().saturating_sub(1));
+
+    loop {
+        thread::sleep(poll_interval);
+
+        // The file may be mid-write; try again on the next poll
+        let update = match watcher.poll() {
+            Ok(update) => update,
+            Err(_) => continue,
+        };
+        if let Some(height) = update.replaced_from {
+            println!("Chain replaced from block #{}", height);
+        }
+        for block in &update.blocks {
+            print_block(block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn each_new_block_is_reported_once() {
+        let dir = std::env::temp_dir().join(format!("blockchain-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let chain_file = dir.join("chain.json").to_string_lossy().into_owned();
+        let accounts_file = dir.join("accounts.json").to_string_lossy().into_owned();
+        let mut blockchain = Blockchain::new("genesis");
+        blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+
+        let mut watcher = BlockWatcher::new(&chain_file).unwrap();
+        assert!(watcher.poll().unwrap().blocks.is_empty());
+
+        for _ in 0..2 {
+            let latest = blockchain.get_latest_block();
+            let block = Block::new(latest.index + 1, Vec::new(), latest.hash.clone(), "validator".to_string());
+            blockchain.chain.push(block);
+        }
+        blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+        let update = watcher.poll().unwrap();
+        let indices: Vec<u32> = update.blocks.iter().map(|block| block.index).collect();
+        assert_eq!(indices, vec![1, 2]);
+        assert_eq!(update.replaced_from, None);
+        assert!(watcher.poll().unwrap().blocks.is_empty());
+
+        blockchain.chain[2].timestamp += 1;
+        blockchain.chain[2].hash = blockchain.chain[2].calculate_hash();
+        blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+        let update = watcher.poll().unwrap();
+        assert_eq!(update.replaced_from, Some(2));
+        assert_eq!(update.blocks.len(), 1);
+    }
+}
@@ -1,9 +1,405 @@
-use crate::models::{Block, Transaction};
+use crate::core::events::ChainEvent;
+use crate::core::proof::{balance_leaf, balance_leaves, BalanceAttestation, BalanceProof};
+use crate::core::{integrity, BlockchainError, ChainConfig, ConsensusMode, GenesisConfig, MempoolPolicy};
+use crate::models::signature::{Signer, Verifier};
+use crate::models::{merkle, Block, BlockHeader, Transaction};
 use ed25519_dalek::{Keypair, PublicKey, SecretKey};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
 use std::fs;
+use std::io::{self, BufRead, Write};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Largest magnitude an f64 holds without losing whole units (2^53)
+const MAX_AMOUNT: f64 = 9_007_199_254_740_992.0;
+
+/// Most windows `timeseries` returns; older activity beyond them is left out
+const MAX_TIMESERIES_BUCKETS: u64 = 10_000;
+
+/// Replaces several files together: every new version is written to a temporary file
+/// beside its target first, and renaming them into place only starts once all are written
+///
+/// If a rename fails, the files already replaced get their previous contents back, so
+/// either every file is updated or none is. A crash between renames is not covered.
+fn replace_files(files: &[(String, String)], retry: &SaveRetry) -> Result<(), String> {
+    let temporaries: Vec<String> = files.iter().map(|(path, _)| format!("{}.tmp", path)).collect();
+    let remove_temporaries = |from: usize| {
+        for temporary in &temporaries[from..] {
+            let _ = fs::remove_file(temporary);
+        }
+    };
+
+    for ((path, contents), temporary) in files.iter().zip(&temporaries) {
+        if let Err(e) = retry.run(|| fs::write(temporary, contents)) {
+            remove_temporaries(0);
+            return Err(format!("Failed to write {}: {}", path, e));
+        }
+    }
+
+    let previous: Vec<Option<Vec<u8>>> = files.iter().map(|(path, _)| fs::read(path).ok()).collect();
+    for (position, ((path, _), temporary)) in files.iter().zip(&temporaries).enumerate() {
+        if let Err(e) = retry.run(|| fs::rename(temporary, path)) {
+            for ((replaced, _), contents) in files[..position].iter().zip(&previous) {
+                let _ = match contents {
+                    Some(contents) => fs::write(replaced, contents),
+                    None => fs::remove_file(replaced),
+                };
+            }
+            remove_temporaries(position);
+            return Err(format!("Failed to replace {}: {}", path, e));
+        }
+    }
+    Ok(())
+}
+
+/// How often a failed save is retried and how long to wait between attempts
+#[derive(Debug, Clone, Copy)]
+pub struct SaveRetry {
+    /// Retries after the first failed attempt (0 disables retrying)
+    pub retries: u32,
+    /// Wait before the first retry, doubled before each later one
+    pub backoff: Duration,
+}
+
+impl Default for SaveRetry {
+    fn default() -> Self {
+        SaveRetry {
+            retries: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl SaveRetry {
+    /// Runs a write, retrying it with exponential backoff until it succeeds or the retries run out
+    fn run<T>(&self, mut write: impl FnMut() -> std::io::Result<T>) -> std::io::Result<T> {
+        let mut backoff = self.backoff;
+        let mut attempt = 0;
+        loop {
+            match write() {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt >= self.retries => return Err(e),
+                Err(e) => {
+                    attempt += 1;
+                    println!("Save failed ({}), retrying in {} ms ({}/{})", e, backoff.as_millis(), attempt, self.retries);
+                    thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                }
+            }
+        }
+    }
+}
+
+/// Whether a keypair's public key derives the address and a test signature from its
+/// secret key verifies against that public key
+pub fn keypair_signs_for(keypair: &Keypair, address: &str) -> bool {
+    let verifier = keypair.verifier();
+    if verifier.address() != address {
+        return false;
+    }
+    let message = format!("keypair check {}", address);
+    match keypair.sign_message(message.as_bytes()) {
+        Ok(signature) => verifier.verify_message(message.as_bytes(), &signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Checks a block's own hash, its link to the previous block and its transaction order
+fn check_block_structure(block: &Block, previous: &Block) -> Result<(), FailureKind> {
+    // Check hash integrity
+    if block.hash != block.calculate_hash() {
+        return Err(FailureKind::Hash);
+    }
+
+    // Check previous hash link
+    if block.previous_hash != previous.hash {
+        return Err(FailureKind::Link);
+    }
+
+    // Check user transactions follow the canonical ordering
+    let user_transactions: Vec<&Transaction> = block
+        .transactions
+        .iter()
+        .filter(|tx| tx.sender != "0")
+        .collect();
+    if user_transactions
+        .windows(2)
+        .any(|pair| canonical_order(pair[0], pair[1]) == Ordering::Greater)
+    {
+        return Err(FailureKind::Ordering);
+    }
+    Ok(())
+}
+
+/// Checks that a block after genesis mints at most one block reward, paid to its validator
+///
+/// Fees move between accounts in `stage_block`, so the reward is the only minting allowed.
+fn check_coinbase(block: &Block, block_reward: f64) -> Result<(), FailureKind> {
+    let mut minting = block.transactions.iter().filter(|tx| tx.sender == "0");
+    let coinbase = match minting.next() {
+        Some(coinbase) => coinbase,
+        None => return Ok(()),
+    };
+    let is_reward = block_reward > 0.0
+        && coinbase.amount == block_reward
+        && coinbase.fee == 0.0
+        && coinbase.recipient == block.validator
+        && coinbase.claim_of.is_none();
+    if !is_reward || minting.next().is_some() {
+        return Err(FailureKind::Coinbase);
+    }
+    Ok(())
+}
+
+/// Adds a change to a balance, rejecting results that are not exactly representable
+fn checked_balance(address: &str, balance: f64, delta: f64) -> Result<f64, BlockchainError> {
+    let result = balance + delta;
+    if !result.is_finite() || result.abs() > MAX_AMOUNT {
+        return Err(BlockchainError::BalanceOverflow { address: address.to_string() });
+    }
+    Ok(result)
+}
+
+/// Callback invoked with a watched transaction and the block that confirmed it
+pub type TransactionCallback = Box<dyn FnMut(&Transaction, &Block)>;
+
+/// Canonical in-block ordering: highest fee first, then oldest first, then sender, then transaction hash
+///
+/// Transactions carry no nonce; the timestamp stands in for it, keeping each sender's
+/// transfers at the same fee in the order they were created.
+fn canonical_order(a: &Transaction, b: &Transaction) -> Ordering {
+    b.fee
+        .partial_cmp(&a.fee)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| a.timestamp.cmp(&b.timestamp))
+        .then_with(|| a.sender.cmp(&b.sender))
+        .then_with(|| a.calculate_hash().cmp(&b.calculate_hash()))
+}
+
+/// The first `max_chars` characters of a string, never splitting a multi-byte character
+fn abbreviate(value: &str, max_chars: usize) -> String {
+    value.chars().take(max_chars).collect()
+}
+
+/// Decodes a keypair stored in the accounts file format ("secret:public" in hex)
+pub fn decode_keypair(address: &str, keypair_str: &str) -> Result<Keypair, String> {
+    // แยก secret และ public key
+    let parts: Vec<&str> = keypair_str.split(':').collect();
+    if parts.len() != 2 {
+        return Err(format!("Invalid keypair format for address: {}", address));
+    }
+    let secret_hex = parts[0];
+    let public_hex = parts[1];
+
+    // แปลง hex เป็น bytes
+    let secret_bytes = hex::decode(secret_hex)
+        .map_err(|_| format!("Invalid secret key hex for address: {}", address))?;
+    let public_bytes = hex::decode(public_hex)
+        .map_err(|_| format!("Invalid public key hex for address: {}", address))?;
+
+    // สร้าง Keypair จาก bytes
+    let public_key = PublicKey::from_bytes(&public_bytes)
+        .map_err(|_| format!("Invalid public key for address: {}", address))?;
+    let secret_key = SecretKey::from_bytes(&secret_bytes)
+        .map_err(|_| format!("Invalid secret key for address: {}", address))?;
+    Ok(Keypair { public: public_key, secret: secret_key })
+}
+
+/// Activity totals for a single account across the confirmed chain
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountStats {
+    pub sent_count: usize,
+    pub received_count: usize,
+    pub volume_sent: f64,
+    pub volume_received: f64,
+    pub first_seen: Option<u32>,
+    /// Lowest balance held after any block since the account first appeared
+    pub min_balance: f64,
+}
+
+/// Serialized size of the chain and its projected growth
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageReport {
+    pub current_size: usize,
+    pub average_block_size: f64,
+    pub target_height: u32,
+    pub projected_size: f64,
+}
+
+/// Economic overview of the pending transaction pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct MempoolSummary {
+    pub transaction_count: usize,
+    pub total_value: f64,
+    pub total_fees: f64,
+    pub fits_in_next_block: usize,
+}
+
+/// Which check a block failed during validation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailureKind {
+    Index,
+    Hash,
+    Link,
+    Ordering,
+    Signature,
+    Validator,
+    Producer,
+    ProducerSignature,
+    Coinbase,
+    Balance,
+    RecordedBalance,
+}
+
+/// The first block that failed validation, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationFailure {
+    pub index: u32,
+    pub kind: FailureKind,
+}
+
+impl fmt::Display for ValidationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            FailureKind::Index => "index does not follow the current tip",
+            FailureKind::Hash => "hash does not match its contents",
+            FailureKind::Link => "previous hash does not match the preceding block",
+            FailureKind::Ordering => "transactions are not in canonical order",
+            FailureKind::Signature => "a transaction signature does not verify",
+            FailureKind::Validator => "produced by an address that was never a validator",
+            FailureKind::Producer => "produced out of turn by a validator that was not scheduled",
+            FailureKind::ProducerSignature => "not signed by the validator that produced it",
+            FailureKind::Coinbase => "mints coins other than a single block reward to its validator",
+            FailureKind::Balance => "a balance goes negative or overflows",
+            FailureKind::RecordedBalance => "recorded balances do not match a replay of the chain",
+        };
+        write!(f, "Block #{}: {}", self.index, reason)
+    }
+}
+
+/// A single break found by `Blockchain::diagnose`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainProblem {
+    /// The stored hash does not match the block's contents
+    HashMismatch { index: u32, expected: String, actual: String },
+    /// The previous-hash field does not point at the preceding block
+    BrokenLink { index: u32, expected: String, actual: String },
+    /// User transactions are not in canonical order
+    TransactionOrder { index: u32 },
+    /// A transaction's signature does not verify against its sender
+    InvalidTransaction { index: u32, tx_hash: String },
+    /// The block was produced by an address that was never a validator
+    UnknownValidator { index: u32, validator: String },
+}
+
+impl ChainProblem {
+    /// How the problem can usually be fixed
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            ChainProblem::HashMismatch { .. } | ChainProblem::BrokenLink { .. } => {
+                "Re-sync from a trusted peer, or rehash the chain if this follows a format migration"
+            }
+            ChainProblem::TransactionOrder { .. } | ChainProblem::InvalidTransaction { .. } => {
+                "The block was tampered with; re-sync from a trusted peer"
+            }
+            ChainProblem::UnknownValidator { .. } => {
+                "Promote the producer to validator if it is legitimate, otherwise re-sync"
+            }
+        }
+    }
+}
+
+impl fmt::Display for ChainProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChainProblem::HashMismatch { index, expected, actual } => write!(
+                f,
+                "Block #{}: hash mismatch (expected {}, stored {})",
+                index, expected, actual
+            ),
+            ChainProblem::BrokenLink { index, expected, actual } => write!(
+                f,
+                "Block #{}: broken previous-hash link (expected {}, stored {})",
+                index, expected, actual
+            ),
+            ChainProblem::TransactionOrder { index } => {
+                write!(f, "Block #{}: transactions are not in canonical order", index)
+            }
+            ChainProblem::InvalidTransaction { index, tx_hash } => {
+                write!(f, "Block #{}: invalid signature on transaction {}", index, tx_hash)
+            }
+            ChainProblem::UnknownValidator { index, validator } => {
+                write!(f, "Block #{}: produced by unknown validator {}", index, validator)
+            }
+        }
+    }
+}
+
+/// Proof of where and when a transaction was confirmed
+#[derive(Clone, Serialize)]
+pub struct Receipt {
+    pub transaction: Transaction,
+    pub tx_hash: String,
+    pub block_index: u32,
+    pub block_hash: String,
+    /// Position of the transaction within its block
+    pub position: usize,
+    pub fee: f64,
+    /// Blocks on top of and including the confirming block at query time
+    pub confirmations: u32,
+}
+
+impl fmt::Display for Receipt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Receipt for {}\n  Block: #{} ({})\n  Position: {}\n  Amount: {:.2}\n  Fee: {:.2}\n  Confirmations: {}",
+            self.tx_hash,
+            self.block_index,
+            self.block_hash,
+            self.position,
+            self.transaction.amount,
+            self.fee,
+            self.confirmations
+        )
+    }
+}
+
+/// Drift between the held keys and the addresses known to the chain
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountReconciliation {
+    /// Chain addresses (accounts, participants, validators) with no keypair held
+    pub missing_keypairs: Vec<String>,
+    /// Held keys whose addresses never appear on the chain
+    pub orphaned_keypairs: Vec<String>,
+    /// Addresses whose registered public key derives a different address
+    pub derivation_mismatches: Vec<String>,
+}
+
+/// User transaction activity confirmed within one time window
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeBucket {
+    /// Start of the window (unix seconds, inclusive)
+    pub start: u64,
+    pub transaction_count: usize,
+    pub volume: f64,
+}
+
+/// A large transfer waiting for its recipient to claim it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingClaim {
+    pub tx_hash: String,
+    pub sender: String,
+    pub recipient: String,
+    pub amount: f64,
+    /// Block that debited the sender
+    pub block_index: u32,
+}
 
 /// The main blockchain data structure
 #[derive(Serialize, Deserialize)]
@@ -14,261 +410,4075 @@ pub struct Blockchain {
     #[serde(skip)]
     pub public_keys: HashMap<String, PublicKey>,
     pub validators: HashMap<String, bool>,
+    /// Transfers above the claim threshold that their recipients have not claimed yet
+    #[serde(default)]
+    pub pending_claims: Vec<PendingClaim>,
     #[serde(skip)]
     pub keypairs: HashMap<String, Arc<Keypair>>,
+    /// Passphrase used to sign the accounts file against tampering (None disables signing)
+    #[serde(skip)]
+    pub accounts_passphrase: Option<String>,
+    /// Retry policy for transient failures while saving
+    #[serde(skip)]
+    pub save_retry: SaveRetry,
+    /// Allows developer shortcuts such as mining empty blocks
+    #[serde(skip)]
+    pub dev_mode: bool,
+    /// Prints why each transaction passes or fails signature checks during chain validation
+    #[serde(skip)]
+    pub verbose_validation: bool,
+    /// Policy parameters, stored alongside the chain data in the same JSON object
+    #[serde(flatten)]
+    config: ChainConfig,
+    /// Recently seen transaction hashes, least recently used first
+    #[serde(skip)]
+    seen_transactions: VecDeque<String>,
+    #[serde(skip)]
+    transaction_watchers: HashMap<String, Vec<TransactionCallback>>,
+    /// Maps each address to the (block index, transaction index) pairs it appears in
+    #[serde(skip)]
+    address_index: HashMap<String, Vec<(u32, usize)>>,
+    /// Lowest balance each address held after any block, kept up to date with the index
+    #[serde(skip)]
+    balance_lows: HashMap<String, f64>,
+    /// Changes made since recording started (None when not recording)
+    #[serde(skip)]
+    event_log: Option<Vec<ChainEvent>>,
 }
 
 impl Blockchain {
     /// Creates a new blockchain with an initial balance for the genesis address
     pub fn new(genesis_address: &str) -> Self {
-        let mut blockchain = Blockchain {
+        Self::from_genesis(&GenesisConfig::single(genesis_address, 1000.0))
+            .expect("A single allocation without a declared supply always reconciles")
+    }
+
+    /// A blockchain with no blocks, accounts or validators under the given policies
+    fn empty(config: ChainConfig) -> Self {
+        Blockchain {
             chain: Vec::new(),
             pending_transactions: Vec::new(),
             accounts: HashMap::new(),
             public_keys: HashMap::new(),
             validators: HashMap::new(),
+            pending_claims: Vec::new(),
             keypairs: HashMap::new(),
-        };
+            accounts_passphrase: None,
+            save_retry: SaveRetry::default(),
+            dev_mode: false,
+            verbose_validation: false,
+            config,
+            seen_transactions: VecDeque::new(),
+            transaction_watchers: HashMap::new(),
+            address_index: HashMap::new(),
+            balance_lows: HashMap::new(),
+            event_log: None,
+        }
+    }
 
-        // Create genesis transaction
-        let genesis_transaction = Transaction::new(
-            "0".to_string(),
-            genesis_address.to_string(),
-            1000.0,
-        );
-        
-        blockchain.pending_transactions.push(genesis_transaction);
-        blockchain.accounts.insert(genesis_address.to_string(), 1000.0);
-        blockchain.create_genesis_block(genesis_address);
-        blockchain
+    /// Creates a new blockchain whose genesis block credits the configured allocations
+    pub fn from_genesis(config: &GenesisConfig) -> Result<Self, String> {
+        let allocations = config.resolve_allocations()?;
+        let mut blockchain = Blockchain::empty(ChainConfig::default());
+
+        let timestamp = config.resolve_timestamp();
+
+        // Create genesis transactions
+        for (address, amount) in &allocations {
+            let mut genesis_transaction = Transaction::new(
+                "0".to_string(),
+                address.to_string(),
+                *amount,
+            );
+            genesis_transaction.timestamp = timestamp;
+            
+            blockchain.pending_transactions.push(genesis_transaction);
+            *blockchain.accounts.entry(address.to_string()).or_insert(0.0) += amount;
+        }
+
+        let genesis_validator = allocations
+            .first()
+            .map(|(address, _)| address.as_str())
+            .unwrap_or("0");
+        blockchain.create_genesis_block(genesis_validator, timestamp);
+        blockchain.rebuild_address_index();
+
+        for validator in &config.validators {
+            blockchain.validators.insert(validator.clone(), true);
+        }
+        Ok(blockchain)
+    }
+
+    /// Active policy parameters
+    pub fn config(&self) -> &ChainConfig {
+        &self.config
+    }
+
+    /// Replaces the policy parameters after checking them
+    ///
+    /// The block reward is fixed once blocks beyond genesis exist, since validation
+    /// checks every block's reward against the current one.
+    pub fn set_config(&mut self, config: ChainConfig) -> Result<(), String> {
+        config.validate()?;
+        if self.chain.len() > 1 && config.block_reward != self.config.block_reward {
+            return Err("The block reward cannot change once blocks have been produced".to_string());
+        }
+        self.config = config.clone();
+        self.record(ChainEvent::ConfigChanged(config));
+        Ok(())
     }
 
     /// Creates the genesis (first) block in the chain
-    pub fn create_genesis_block(&mut self, genesis_address: &str) {
-        let genesis_block = Block::new(
+    pub fn create_genesis_block(&mut self, genesis_address: &str, timestamp: u64) {
+        let genesis_block = Block::with_timestamp(
             0, 
             self.pending_transactions.clone(), 
             "0".to_string(),
-            genesis_address.to_string()
+            genesis_address.to_string(),
+            timestamp,
         );
         self.chain.push(genesis_block);
         self.pending_transactions.clear();
     }
 
+    /// Returns the accounts credited by the genesis block and their initial balances
+    pub fn genesis_distribution(&self) -> Vec<(String, f64)> {
+        let mut distribution: Vec<(String, f64)> = Vec::new();
+        let genesis_block = match self.chain.first() {
+            Some(block) => block,
+            None => return distribution,
+        };
+
+        for tx in &genesis_block.transactions {
+            match distribution.iter_mut().find(|(address, _)| *address == tx.recipient) {
+                Some((_, amount)) => *amount += tx.amount,
+                None => distribution.push((tx.recipient.clone(), tx.amount)),
+            }
+        }
+        distribution
+    }
+
+    /// Genesis-credited addresses whose registered key derives a different address
+    pub fn genesis_key_mismatches(&self) -> Vec<String> {
+        self.genesis_distribution()
+            .into_iter()
+            .map(|(address, _)| address)
+            .filter(|address| match self.public_key_for(address) {
+                Some(public_key) => public_key.address() != *address,
+                None => false,
+            })
+            .collect()
+    }
+
+    /// Returns a one-line summary of every block in chain order
+    pub fn chain_summary(&self) -> Vec<String> {
+        self.chain
+            .iter()
+            .map(|block| {
+                format!(
+                    "#{} {} txs={} validator={} time={}",
+                    block.index,
+                    abbreviate(&block.hash, 12),
+                    block.transactions.len(),
+                    abbreviate(&block.validator, 12),
+                    block.timestamp
+                )
+            })
+            .collect()
+    }
+
+    /// Checks via a Merkle proof whether a transaction hash is included in a block
+    pub fn verify_transaction_inclusion(&self, block_index: u32, tx_hash: &str) -> Result<bool, String> {
+        let block = self
+            .chain
+            .get(block_index as usize)
+            .ok_or_else(|| format!("Block #{} not found", block_index))?;
+
+        Ok(match block.merkle_proof(tx_hash) {
+            Some(proof) => merkle::verify_proof(tx_hash, &proof, &block.merkle_root()),
+            None => false,
+        })
+    }
+
+    /// Headers of every block in chain order, for light clients that skip transaction data
+    pub fn export_headers(&self) -> Vec<BlockHeader> {
+        self.replay_headers().0
+    }
+
+    /// Replays the chain into headers, also returning the balances after the last block
+    fn replay_headers(&self) -> (Vec<BlockHeader>, HashMap<String, f64>) {
+        let mut replay = Blockchain::empty(self.config.clone());
+        let headers = self
+            .chain
+            .iter()
+            .map(|block| {
+                // A block that does not apply leaves the balances as they were; validation reports it
+                let _ = replay.apply_block(block);
+                block.header(merkle::merkle_root(&balance_leaves(&replay.accounts)))
+            })
+            .collect();
+        (headers, replay.accounts)
+    }
+
+    /// Proves an address's balance at the tip with a Merkle path to the tip header's balances root
+    pub fn balance_proof(&self, address: &str) -> Result<BalanceProof, String> {
+        let (mut headers, balances) = self.replay_headers();
+        let balance = *balances
+            .get(address)
+            .ok_or_else(|| format!("{} has no balance on chain", address))?;
+
+        let leaves = balance_leaves(&balances);
+        let leaf = balance_leaf(address, balance);
+        let proof = leaves
+            .iter()
+            .position(|candidate| *candidate == leaf)
+            .and_then(|position| merkle::merkle_proof(&leaves, position))
+            .ok_or_else(|| format!("No balance leaf for {}", address))?;
+
+        Ok(BalanceProof {
+            address: address.to_string(),
+            balance,
+            header: headers.pop().expect("Chain should not be empty"),
+            proof,
+        })
+    }
+
+    /// Signs the signer's current balance and sent count as of the latest block
+    pub fn sign_balance_attestation(&self, signer: &dyn Signer) -> Result<BalanceAttestation, String> {
+        let address = signer.verifier().address();
+        let latest = self.get_latest_block();
+        let mut attestation = BalanceAttestation {
+            balance: self.get_account_balance(&address),
+            nonce: self.account_stats(&address).sent_count,
+            block_index: latest.index,
+            block_hash: latest.hash.clone(),
+            address,
+            signature: None,
+        };
+        attestation.sign(signer)?;
+        Ok(attestation)
+    }
+
+    /// Checks an attestation's signature, its block, and that it matches the account's current state
+    pub fn verify_balance_attestation(&self, attestation: &BalanceAttestation) -> Result<(), String> {
+        let key = self
+            .verifying_key(&attestation.address)
+            .ok_or_else(|| format!("No public key known for {}", attestation.address))?;
+        if !attestation.verify_signature(&key) {
+            return Err("Attestation signature does not match the account's key".to_string());
+        }
+
+        let on_chain = self
+            .chain
+            .get(attestation.block_index as usize)
+            .is_some_and(|block| block.hash == attestation.block_hash);
+        if !on_chain {
+            return Err(format!("Block #{} with the attested hash is not on this chain", attestation.block_index));
+        }
+
+        let balance = self.get_account_balance(&attestation.address);
+        if balance != attestation.balance {
+            return Err(format!(
+                "Attested balance {:.2} does not match the on-chain balance {:.2}",
+                attestation.balance, balance
+            ));
+        }
+        let nonce = self.account_stats(&attestation.address).sent_count;
+        if nonce != attestation.nonce {
+            return Err(format!(
+                "Attested nonce {} does not match the {} transactions sent on chain",
+                attestation.nonce, nonce
+            ));
+        }
+        Ok(())
+    }
+
     /// Returns a reference to the most recent block
     pub fn get_latest_block(&self) -> &Block {
         self.chain.last().expect("Chain should not be empty")
     }
 
     /// Adds a transaction to the pending transactions pool
-    pub fn add_transaction(&mut self, mut transaction: Transaction, keypair: &Keypair) -> Result<(), String> {
-        // Check if sender has enough balance (except for genesis transactions)
-        if transaction.sender != "0" {
-            let sender_balance = self.accounts.get(&transaction.sender).unwrap_or(&0.0);
-            if *sender_balance < transaction.amount {
-                return Err("Insufficient balance for transaction".to_string());
+    ///
+    /// Minting transactions (sender `"0"`) are only ever created by blocks and are rejected.
+    pub fn add_transaction(&mut self, mut transaction: Transaction, signer: &dyn Signer) -> Result<(), BlockchainError> {
+        if transaction.sender == "0" {
+            return Err(BlockchainError::ReservedSender);
+        }
+
+        // Resubmissions of a recently seen transaction are dropped without complaint
+        if self.mark_seen(&transaction.calculate_hash()) {
+            return Ok(());
+        }
+
+        // Check if sender has enough balance
+        self.check_transfer(&transaction)?;
+
+        // Sign the transaction
+        transaction
+            .sign(signer)
+            .map_err(BlockchainError::SigningFailed)?;
+
+        self.enqueue(transaction)
+    }
+
+    /// Adds a transaction that was signed elsewhere (e.g. on an offline machine) to the pool
+    pub fn submit_signed_transaction(&mut self, transaction: Transaction) -> Result<(), BlockchainError> {
+        // Only blocks may mint coins, so outside submissions can never use the genesis sender
+        if transaction.sender == "0" {
+            return Err(BlockchainError::ReservedSender);
+        }
+        if !self.has_valid_signature(&transaction) {
+            return Err(BlockchainError::InvalidSignature);
+        }
+        // A signed file can be submitted again; never let it spend twice
+        let tx_hash = transaction.calculate_hash();
+        let already_known = self.pending_transactions.iter().any(|tx| tx.calculate_hash() == tx_hash)
+            || self
+                .indexed_transactions(&transaction.sender)
+                .any(|(_, tx)| tx.calculate_hash() == tx_hash);
+        if already_known {
+            return Err(BlockchainError::DuplicateTransaction);
+        }
+        self.check_transfer(&transaction)?;
+        self.enqueue(transaction)
+    }
+
+    /// Admits transactions from another node's mempool, returning how many were accepted
+    ///
+    /// Each one goes through the same checks as a signed submission. Transactions that
+    /// would overdraw their sender together with its already pending spends are skipped.
+    pub fn merge_mempool(&mut self, other: &[Transaction]) -> usize {
+        let mut admitted = 0;
+        for tx in other {
+            let pending_spend: f64 = self
+                .pending_transactions
+                .iter()
+                .filter(|pending| pending.sender == tx.sender)
+                .map(|pending| pending.amount + pending.fee)
+                .sum();
+            if self.spendable_balance(&tx.sender) < pending_spend + tx.amount + tx.fee {
+                continue;
             }
-            
-            // Sign the transaction
-            transaction.sign(keypair).map_err(|e| e.to_string())?;
+            if self.submit_signed_transaction(tx.clone()).is_ok() {
+                admitted += 1;
+            }
+        }
+        admitted
+    }
+
+    /// Places a checked transaction in the mempool, making room if it is full
+    fn enqueue(&mut self, transaction: Transaction) -> Result<(), BlockchainError> {
+        // Make room in a full mempool according to the policy
+        if self.config.max_mempool_size > 0 && self.pending_transactions.len() >= self.config.max_mempool_size {
+            self.make_mempool_room(&transaction)?;
         }
 
         // Add to pending transactions
+        self.remember_seen(transaction.calculate_hash());
+        self.record(ChainEvent::TransactionAdded(transaction.clone()));
         self.pending_transactions.push(transaction);
         Ok(())
     }
 
-    /// Registers a keypair with the blockchain and returns the associated address
-    pub fn register_keypair(&mut self, keypair: Keypair) -> String {
-        let address = hex::encode(keypair.public.as_bytes());
-        
-        // Store public key
-        self.public_keys.insert(address.clone(), keypair.public);
-        
-        // Store keypair
-        self.keypairs.insert(address.clone(), Arc::new(keypair));
-        
-        // Initialize account balance to zero
-        self.accounts.entry(address.clone()).or_insert(0.0);
-        
-        address
+    /// Returns whether the hash was seen recently, refreshing its position if so
+    fn mark_seen(&mut self, tx_hash: &str) -> bool {
+        match self.seen_transactions.iter().position(|seen| seen == tx_hash) {
+            Some(position) => {
+                if let Some(hash) = self.seen_transactions.remove(position) {
+                    self.seen_transactions.push_back(hash);
+                }
+                true
+            }
+            None => false,
+        }
     }
 
-    /// Adds an account to the validator set
-    pub fn add_validator(&mut self, address: String) -> Result<(), String> {
-        if !self.public_keys.contains_key(&address) {
-            return Err(format!("Address {} is not registered", address));
+    /// Records a hash as most recently seen, evicting the least recently used beyond capacity
+    fn remember_seen(&mut self, tx_hash: String) {
+        if self.config.seen_cache_size == 0 {
+            return;
+        }
+        if !self.mark_seen(&tx_hash) {
+            self.seen_transactions.push_back(tx_hash);
+        }
+        while self.seen_transactions.len() > self.config.seen_cache_size {
+            self.seen_transactions.pop_front();
+        }
+    }
+
+    /// Applies the dust, fee and balance rules a non-genesis transfer must pass
+    fn check_transfer(&self, transaction: &Transaction) -> Result<(), BlockchainError> {
+        self.check_rules(transaction)?;
+
+        // Claims are exempt: a held transfer may be the first funds a new account receives
+        if self.config.reject_unfunded_senders && transaction.claim_of.is_none() && self.is_unfunded(&transaction.sender) {
+            return Err(BlockchainError::UnfundedSender { address: transaction.sender.clone() });
+        }
+
+        // Rewards that have not matured yet cannot be spent
+        if self.spendable_balance(&transaction.sender) < transaction.amount + transaction.fee {
+            return Err(BlockchainError::InsufficientBalance);
         }
-        self.validators.insert(address, true);
         Ok(())
     }
 
-    /// Checks if an address is a validator
-    pub fn is_validator(&self, address: &str) -> bool {
-        *self.validators.get(address).unwrap_or(&false)
+    /// Whether the address holds nothing and has never received a confirmed transfer from someone else
+    fn is_unfunded(&self, address: &str) -> bool {
+        self.get_account_balance(address) == 0.0
+            && !self
+                .indexed_transactions(address)
+                .any(|(_, tx)| tx.recipient == address && tx.sender != address)
     }
 
-    /// Creates a new block containing the pending transactions
-    pub fn create_block(&mut self, validator_address: &str) -> Result<Block, String> {
-        // Ensure validator authorization
-        if !self.is_validator(validator_address) {
-            return Err("Only authorized validators can create blocks".to_string());
+    /// Applies every admission rule except the sender's balance
+    fn check_rules(&self, transaction: &Transaction) -> Result<(), BlockchainError> {
+        // Coins sent to the minting address would vanish from the supply unaccounted
+        if transaction.recipient == "0" {
+            return Err(BlockchainError::ReservedRecipient);
         }
 
-        // Ensure there are transactions to include
-        if self.pending_transactions.is_empty() {
-            return Err("No pending transactions to include in block".to_string());
+        // Amounts must be plain, non-negative numbers the balance type holds exactly
+        for value in [transaction.amount, transaction.fee] {
+            if !value.is_finite() || !(0.0..=MAX_AMOUNT).contains(&value) {
+                return Err(BlockchainError::AmountOutOfRange { amount: value });
+            }
         }
+        checked_balance(
+            &transaction.recipient,
+            self.get_account_balance(&transaction.recipient),
+            transaction.amount,
+        )?;
 
-        // Create new block
-        let block = Block::new(
-            self.chain.len() as u32,
-            self.pending_transactions.clone(),
-            self.get_latest_block().hash.clone(),
-            validator_address.to_string(),
-        );
+        // A claim moves no coins of its own, so only the claim and its fee are checked
+        if let Some(claim_of) = &transaction.claim_of {
+            self.claim_for(claim_of, &transaction.sender)?;
+            return self.check_fee(transaction);
+        }
 
-        // Update chain
-        self.chain.push(block.clone());
+        // Reject dust transfers below the configured threshold
+        if transaction.amount < self.config.min_transfer {
+            return Err(BlockchainError::DustAmount {
+                amount: transaction.amount,
+                minimum: self.config.min_transfer,
+            });
+        }
 
-        // Update account balances
-        self.apply_transactions();
+        if self.config.require_memo && transaction.memo.is_none() {
+            return Err(BlockchainError::MemoRequired);
+        }
 
-        // Clear pending transactions
-        self.pending_transactions.clear();
+        // Limit the blast radius of a single transfer
+        if let Some(maximum) = self.config.max_transaction_amount {
+            if transaction.amount > maximum {
+                return Err(BlockchainError::AmountTooLarge {
+                    amount: transaction.amount,
+                    maximum,
+                });
+            }
+        }
 
-        Ok(block)
+        self.check_fee(transaction)
     }
 
-    /// Applies all pending transactions to account balances
-    fn apply_transactions(&mut self) {
-        for tx in &self.pending_transactions {
-            // Debit sender (except genesis)
-            if tx.sender != "0" {
-                *self.accounts.entry(tx.sender.clone()).or_insert(0.0) -= tx.amount;
+    /// Catches accidental oversized fees
+    fn check_fee(&self, transaction: &Transaction) -> Result<(), BlockchainError> {
+        if let Some(max_fee) = self.config.max_fee {
+            if transaction.fee > max_fee {
+                return Err(BlockchainError::FeeTooHigh {
+                    fee: transaction.fee,
+                    maximum: max_fee,
+                });
             }
-            
-            // Credit recipient
-            *self.accounts.entry(tx.recipient.clone()).or_insert(0.0) += tx.amount;
         }
+        Ok(())
     }
 
-    /// Validates the entire blockchain
-    pub fn validate_chain(&self) -> bool {
-        // Empty chain is valid
-        if self.chain.is_empty() {
-            return true;
-        }
-        
-        // Validate each block starting from the second one
-        for i in 1..self.chain.len() {
-            let current_block = &self.chain[i];
-            let previous_block = &self.chain[i - 1];
+    /// Re-checks each pending transaction against the current state, by index into the pool
+    ///
+    /// Transactions are checked in the order a block applies them, so earlier pending
+    /// transfers count towards the balances later ones see.
+    pub fn pending_validity(&self) -> Vec<(usize, Result<(), BlockchainError>)> {
+        let mut order: Vec<usize> = (0..self.pending_transactions.len()).collect();
+        order.sort_by(|a, b| canonical_order(&self.pending_transactions[*a], &self.pending_transactions[*b]));
 
-            // Check hash integrity
-            if current_block.hash != current_block.calculate_hash() {
-                print!("hash integrity failed");
-                return false;
+        let mut balances: HashMap<&str, f64> = HashMap::new();
+        let mut results = Vec::with_capacity(order.len());
+        for index in order {
+            let tx = &self.pending_transactions[index];
+            if tx.sender == "0" {
+                results.push((index, Ok(())));
+                continue;
             }
 
-            // Check previous hash link
-            if current_block.previous_hash != previous_block.hash {
-                print!("previous hash link failed");
-                return false;
-            }
+            let available = *balances
+                .entry(tx.sender.as_str())
+                .or_insert_with(|| self.spendable_balance(&tx.sender));
+            let result = if !self.has_valid_signature(tx) {
+                Err(BlockchainError::InvalidSignature)
+            } else if let Err(e) = self.check_rules(tx) {
+                Err(e)
+            } else if available < tx.amount + tx.fee {
+                Err(BlockchainError::InsufficientBalance)
+            } else {
+                Ok(())
+            };
 
-            // Validate all transactions in the block
-            for tx in &current_block.transactions {
-                if !tx.is_valid() {
-                    print!("Validate all transactions in the block failed :");
-                    return false;
+            if result.is_ok() {
+                balances.insert(tx.sender.as_str(), available - tx.amount - tx.fee);
+                if !self.requires_claim(tx) {
+                    let recipient = balances
+                        .entry(tx.recipient.as_str())
+                        .or_insert_with(|| self.spendable_balance(&tx.recipient));
+                    *recipient += tx.amount;
                 }
             }
+            results.push((index, result));
+        }
+
+        results.sort_by_key(|(index, _)| *index);
+        results
+    }
 
-            // Check if the block was created by a valid validator
-            if !self.validators.get(&current_block.validator).unwrap_or(&false) {
-                print!("created by a valid validator failed");
-                return false;
+    /// Whether the transfer is held until its recipient claims it
+    pub fn requires_claim(&self, transaction: &Transaction) -> bool {
+        match self.config.claim_threshold {
+            Some(threshold) => {
+                transaction.sender != "0"
+                    && transaction.sender != transaction.recipient
+                    && transaction.claim_of.is_none()
+                    && transaction.amount > threshold
             }
+            None => false,
         }
-
-        true
     }
 
-    /// Gets the balance for an account
-    pub fn get_account_balance(&self, address: &str) -> f64 {
-        *self.accounts.get(address).unwrap_or(&0.0)
+    /// Finds the held transfer the claimant may claim
+    fn claim_for(&self, tx_hash: &str, claimant: &str) -> Result<&PendingClaim, BlockchainError> {
+        self.pending_claims
+            .iter()
+            .find(|claim| claim.tx_hash == tx_hash && claim.recipient == claimant)
+            .ok_or_else(|| BlockchainError::UnknownClaim { tx_hash: tx_hash.to_string() })
     }
-    
-    /// Saves the blockchain to files
-    pub fn save_to_file(&self, filename: &str, accounts_file: &str) -> Result<(), String> {
-        let blockchain_json = serde_json::to_string_pretty(self)
-            .map_err(|e| format!("Failed to serialize: {}", e))?;
-    
-        fs::write(filename, blockchain_json)
-            .map_err(|e| format!("Failed to write file: {}", e))?;
-    
-    // การบันทึก keypair (public + secret key ทั้งหมดในรูปแบบ hex)
-        let accounts_json: HashMap<String, String> = self.keypairs.iter()
-            .map(|(address, keypair)| {
-                let secret_hex = hex::encode(keypair.secret.as_bytes()); // แปลง secret key เป็น hex
-                let public_hex = hex::encode(keypair.public.as_bytes()); // แปลง public key เป็น hex
-                (address.clone(), format!("{}:{}", secret_hex, public_hex)) // เก็บไว้ในรูปแบบ secret:public
-            })
-            .collect();
+
+    /// Queues a signed claim for a held transfer to the signer's address
+    pub fn claim_transfer(&mut self, tx_hash: &str, fee: f64, signer: &dyn Signer) -> Result<(), BlockchainError> {
+        let claimant = signer.verifier().address();
+        let already_pending = self
+            .pending_transactions
+            .iter()
+            .any(|tx| tx.claim_of.as_deref() == Some(tx_hash));
+        if already_pending {
+            return Err(BlockchainError::DuplicateTransaction);
+        }
+
+        let transaction = Transaction::claim(claimant, tx_hash).with_fee(fee);
+        self.add_transaction(transaction, signer)
+    }
+
+    /// Block height at which an unclaimed transfer returns to its sender
+    pub fn claim_expiry(&self, claim: &PendingClaim) -> u32 {
+        claim.block_index.saturating_add(self.config.claim_expiry_blocks)
+    }
+
+    /// Total a sender needs to cover the given transfers, amounts plus fees
+    pub fn required_balance(&self, transactions: &[Transaction]) -> f64 {
+        transactions.iter().map(|tx| tx.amount + tx.fee).sum()
+    }
+
+    /// Projects the sender and recipient balances after the transaction, without changing any state
+    pub fn simulate(&self, transaction: &Transaction) -> Result<(f64, f64), BlockchainError> {
+        if transaction.sender != "0" {
+            self.check_transfer(transaction)?;
+        }
+
+        let mut sender_balance = self.get_account_balance(&transaction.sender);
+        let mut recipient_balance = self.get_account_balance(&transaction.recipient);
+        if transaction.sender == transaction.recipient {
+            // A self-transfer only costs the fee
+            sender_balance -= transaction.fee;
+            recipient_balance = sender_balance;
+        } else {
+            if transaction.sender != "0" {
+                sender_balance -= transaction.amount + transaction.fee;
+            }
+            if !self.requires_claim(transaction) {
+                recipient_balance += transaction.amount;
+            }
+        }
+        Ok((sender_balance, recipient_balance))
+    }
+
+    /// Frees a mempool slot for the incoming transaction or rejects it
+    fn make_mempool_room(&mut self, incoming: &Transaction) -> Result<(), BlockchainError> {
+        let full = BlockchainError::MempoolFull { capacity: self.config.max_mempool_size };
+        if self.config.mempool_policy == MempoolPolicy::RejectNew {
+            return Err(full);
+        }
+
+        let lowest = self
+            .pending_transactions
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.fee.partial_cmp(&b.fee).unwrap_or(Ordering::Equal))
+            .map(|(position, tx)| (position, tx.fee));
+
+        match lowest {
+            Some((position, fee)) if incoming.fee > fee => {
+                let evicted = self.pending_transactions.remove(position);
+                println!("Evicted pending transaction {} (fee {:.2})", evicted.calculate_hash(), evicted.fee);
+                Ok(())
+            }
+            _ => Err(full),
+        }
+    }
+
+    /// Registers a keypair with the blockchain and returns the associated address
+    pub fn register_keypair(&mut self, keypair: Keypair) -> String {
+        let address = keypair.verifier().address();
         
-        // Serialize the accounts data into a pretty JSON format
-        let pretty_json = serde_json::to_string_pretty(&accounts_json)
-            .map_err(|e| format!("Failed to serialize accounts: {}", e))?;
+        // Store public key
+        self.public_keys.insert(address.clone(), keypair.public);
+        
+        // Store keypair
+        self.keypairs.insert(address.clone(), Arc::new(keypair));
+        
+        // Initialize account balance to zero
+        self.accounts.entry(address.clone()).or_insert(0.0);
+        self.record(ChainEvent::AccountRegistered(address.clone()));
+        
+        address
+    }
+
+    /// Whether the held keypair for an address actually signs for it
+    pub fn verify_keypair(&self, address: &str) -> bool {
+        self.keypairs
+            .get(address)
+            .is_some_and(|keypair| keypair_signs_for(keypair, address))
+    }
 
-        // Write the pretty JSON to the accounts file
-        fs::write(accounts_file, pretty_json)
-            .map_err(|e| format!("Unable to write accounts to file: {}", e))?;
+    /// Gives every address with a held keypair or known public key a balance entry, at zero if missing
+    pub fn ensure_account_entries(&mut self) {
+        let addresses: Vec<String> = self.keypairs.keys().chain(self.public_keys.keys()).cloned().collect();
+        for address in addresses {
+            self.accounts.entry(address).or_insert(0.0);
+        }
+    }
 
+    /// Adds an account to the validator set
+    ///
+    /// Validators don't need a balance; unfunded ones still get a selection weight of 1.
+    pub fn add_validator(&mut self, address: String) -> Result<(), String> {
+        self.check_registered(&address).map_err(|e| e.to_string())?;
+        self.validators.insert(address.clone(), true);
+        self.record(ChainEvent::ValidatorAdded(address));
         Ok(())
     }
-    
-    
-    pub fn load_from_file(blockchain_file: &str, accounts_file: &str) -> Result<Self, String> {
-        let blockchain_data = fs::read_to_string(blockchain_file)
-            .map_err(|_| format!("Failed to read blockchain file: {}", blockchain_file))?;
-        let mut blockchain: Blockchain = serde_json::from_str(&blockchain_data)
-            .map_err(|_| "Failed to parse blockchain file".to_string())?;
-    
-        let accounts_data = fs::read_to_string(accounts_file)
-            .map_err(|_| format!("Failed to read accounts file: {}", accounts_file))?;
-        let accounts: HashMap<String, String> = serde_json::from_str(&accounts_data)
-            .map_err(|_| "Failed to parse accounts file".to_string())?;
-    
-        for (address, keypair_str) in accounts {
-            // แยก secret และ public key
-            let parts: Vec<&str> = keypair_str.split(':').collect();
-            if parts.len() != 2 {
-                return Err(format!("Invalid keypair format for address: {}", address));
+
+    /// Adds several accounts to the validator set, returning a result per address in order
+    ///
+    /// Registered addresses are promoted even if others fail, unless `atomic` is set, in
+    /// which case any failure leaves the validator set unchanged.
+    pub fn add_validators(&mut self, addresses: &[String], atomic: bool) -> Vec<Result<(), BlockchainError>> {
+        let results: Vec<Result<(), BlockchainError>> =
+            addresses.iter().map(|address| self.check_registered(address)).collect();
+        if atomic && results.iter().any(Result::is_err) {
+            return results;
+        }
+
+        for (address, result) in addresses.iter().zip(&results) {
+            if result.is_ok() {
+                self.validators.insert(address.clone(), true);
+                self.record(ChainEvent::ValidatorAdded(address.clone()));
             }
-            let secret_hex = parts[0];
-            let public_hex = parts[1];
-    
-            // แปลง hex เป็น bytes
-            let secret_bytes = hex::decode(secret_hex)
-                .map_err(|_| format!("Invalid secret key hex for address: {}", address))?;
-            let public_bytes = hex::decode(public_hex)
-                .map_err(|_| format!("Invalid public key hex for address: {}", address))?;
-    
-            // สร้าง Keypair จาก bytes
-            let public_key = PublicKey::from_bytes(&public_bytes)
-                .map_err(|_| format!("Invalid public key for address: {}", address))?;
-            let secret_key = SecretKey::from_bytes(&secret_bytes)
-                .map_err(|_| format!("Invalid secret key for address: {}", address))?;
-            let keypair = Keypair { public: public_key, secret: secret_key };
-    
-            println!("Loaded account: {}", address);
-            blockchain.keypairs.insert(address.clone(), Arc::new(keypair));
         }
-    
-        Ok(blockchain)
+        results
     }
-    
-    
-    
-}
\ No newline at end of file
+
+    fn check_registered(&self, address: &str) -> Result<(), BlockchainError> {
+        match self.public_key_for(address) {
+            Some(_) => Ok(()),
+            None => Err(BlockchainError::UnregisteredAddress { address: address.to_string() }),
+        }
+    }
+
+    /// Hands validator status from one account to another in a single step
+    pub fn transfer_validator_role(&mut self, from: &str, to: &str) -> Result<(), String> {
+        if !self.is_validator(from) {
+            return Err(format!("Address {} is not a validator", from));
+        }
+        if self.public_key_for(to).is_none() {
+            return Err(format!("Address {} is not registered", to));
+        }
+        if from == to {
+            return Err("Cannot transfer the validator role to the same account".to_string());
+        }
+
+        // Keep the old validator as a retired entry so its past blocks stay valid
+        self.validators.insert(to.to_string(), true);
+        self.validators.insert(from.to_string(), false);
+
+        // Never leave the chain without anyone able to produce blocks
+        if !self.validators.values().any(|active| *active) {
+            self.validators.insert(from.to_string(), true);
+            return Err("Transfer would leave no validators".to_string());
+        }
+        self.record(ChainEvent::ValidatorTransferred { from: from.to_string(), to: to.to_string() });
+        Ok(())
+    }
+
+    /// Returns the active validators in address order
+    fn active_validators(&self) -> Vec<String> {
+        let mut validators: Vec<String> = self
+            .validators
+            .iter()
+            .filter(|(_, active)| **active)
+            .map(|(address, _)| address.clone())
+            .collect();
+        validators.sort();
+        validators
+    }
+
+    /// Stake used for producer selection, floored at 1 so unfunded validators can be chosen
+    fn selection_weight(&self, address: &str) -> f64 {
+        self.get_account_balance(address).max(1.0)
+    }
+
+    /// Stake-weighted selection weight of an active validator, or 0 for anyone else
+    ///
+    /// Stake is the account balance; there is no separate bonded stake.
+    pub fn validator_weight(&self, address: &str) -> f64 {
+        if self.is_validator(address) {
+            self.selection_weight(address)
+        } else {
+            0.0
+        }
+    }
+
+    /// Sum of the weights of all active validators
+    pub fn total_validator_weight(&self) -> f64 {
+        self.active_validators().iter().map(|v| self.selection_weight(v)).sum()
+    }
+
+    /// Chooses the producer of the next block, or None when any validator may produce it
+    pub fn next_block_producer(&self) -> Option<String> {
+        let validators = self.active_validators();
+        if validators.is_empty() {
+            return None;
+        }
+        let height = self.chain.len();
+
+        match self.config.consensus_mode {
+            ConsensusMode::Open => None,
+            ConsensusMode::RoundRobin => Some(validators[height % validators.len()].clone()),
+            ConsensusMode::StakeWeighted => {
+                let digest = self.consensus_seed();
+                let mut seed_bytes = [0u8; 8];
+                seed_bytes.copy_from_slice(&digest[..8]);
+                let seed = u64::from_be_bytes(seed_bytes) as f64 / u64::MAX as f64;
+
+                let total_weight = self.total_validator_weight();
+                let mut target = seed * total_weight;
+                for validator in &validators {
+                    target -= self.selection_weight(validator);
+                    if target <= 0.0 {
+                        return Some(validator.clone());
+                    }
+                }
+                validators.last().cloned()
+            }
+        }
+    }
+
+    /// Seed of the next stake-weighted draw: a hash of the tip's hash and the next height
+    ///
+    /// Everything the draw uses (this seed, the validator set and the balances) is saved
+    /// with the chain, so every node and every reload of the file picks the same producer.
+    pub fn consensus_seed(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.get_latest_block().hash.as_bytes());
+        hasher.update(self.chain.len().to_string().as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Looks up the public key registered for an address
+    pub fn public_key_for(&self, address: &str) -> Option<PublicKey> {
+        self.public_keys
+            .get(address)
+            .copied()
+            .or_else(|| self.keypairs.get(address).map(|keypair| keypair.public))
+    }
+
+    /// Returns the key to verify an address's signatures with
+    ///
+    /// Addresses are hex-encoded public keys, so the key can be recovered from the
+    /// address itself when no key material has been loaded (e.g. in observer mode).
+    fn verifying_key(&self, address: &str) -> Option<PublicKey> {
+        self.public_key_for(address).or_else(|| {
+            let bytes = hex::decode(address).ok()?;
+            PublicKey::from_bytes(&bytes).ok()
+        })
+    }
+
+    /// Checks that a transaction's signature still matches its contents
+    fn has_valid_signature(&self, tx: &Transaction) -> bool {
+        if tx.sender == "0" {
+            return true;
+        }
+        match self.verifying_key(&tx.sender) {
+            Some(public_key) => tx.verify_signature(&public_key),
+            None => false,
+        }
+    }
+
+    /// Checks if an address is a validator
+    pub fn is_validator(&self, address: &str) -> bool {
+        *self.validators.get(address).unwrap_or(&false)
+    }
+
+    /// Creates a new block containing the pending transactions
+    pub fn create_block(&mut self, validator_address: &str) -> Result<Block, String> {
+        self.check_producer(validator_address).map_err(|e| e.to_string())?;
+        let keypair = self.producer_key(validator_address).map_err(|e| e.to_string())?;
+
+        // Drop transactions that were mutated after signing or no longer fit the current state
+        let rejected: Vec<Transaction> = self
+            .pending_validity()
+            .into_iter()
+            .filter(|(_, result)| result.is_err())
+            .map(|(index, _)| self.pending_transactions[index].clone())
+            .collect();
+        if !rejected.is_empty() {
+            println!("Dropping {} transaction(s) that are no longer valid", rejected.len());
+            self.remove_pending(&rejected);
+            self.record(ChainEvent::PendingDropped(rejected));
+        }
+
+        let mut block = self.preview_block(validator_address).map_err(|e| e.to_string())?;
+        block.sign(keypair.as_ref())?;
+
+        // Update account balances, then the chain
+        self.apply_block(&block).map_err(|e| e.to_string())?;
+        self.chain.push(block.clone());
+        self.record(ChainEvent::BlockAdded(block.clone()));
+
+        // Notify subscribers watching transactions in this block
+        self.notify_watchers(&block);
+
+        // Remove only the included transactions, leaving the rest for later blocks
+        self.remove_pending(&block.transactions);
+
+        Ok(block)
+    }
+
+    /// Validates a block received from elsewhere against the tip and appends it
+    pub fn append_block(&mut self, block: Block) -> Result<(), BlockchainError> {
+        let fail = |kind| BlockchainError::InvalidBlock(ValidationFailure { index: block.index, kind });
+
+        if block.index as usize != self.chain.len() {
+            return Err(fail(FailureKind::Index));
+        }
+        check_block_structure(&block, self.get_latest_block()).map_err(fail)?;
+        check_coinbase(&block, self.config.block_reward).map_err(fail)?;
+        if !block.transactions.iter().all(|tx| self.has_valid_signature(tx)) {
+            return Err(fail(FailureKind::Signature));
+        }
+        if !self.is_validator(&block.validator) {
+            return Err(fail(FailureKind::Validator));
+        }
+        if self.next_block_producer().is_some_and(|producer| producer != block.validator) {
+            return Err(fail(FailureKind::Producer));
+        }
+        if !self.has_valid_producer_signature(&block) {
+            return Err(fail(FailureKind::ProducerSignature));
+        }
+        let (updated, _) = self.stage_block(&block)?;
+        if updated.values().any(|balance| *balance < 0.0) {
+            return Err(fail(FailureKind::Balance));
+        }
+
+        self.apply_block(&block)?;
+        self.chain.push(block.clone());
+        self.record(ChainEvent::BlockAdded(block.clone()));
+        self.notify_watchers(&block);
+        self.remove_pending(&block.transactions);
+        Ok(())
+    }
+
+    /// Appends `count` blocks holding only the block reward, for advancing the height in development
+    ///
+    /// Requires `dev_mode`. Pending transactions are left in the pool, and each block must
+    /// still be the validator's turn to produce.
+    pub fn mine_empty_blocks(&mut self, count: usize, validator_address: &str) -> Result<Vec<Block>, BlockchainError> {
+        if !self.dev_mode {
+            return Err(BlockchainError::DevModeRequired);
+        }
+
+        let keypair = self.producer_key(validator_address)?;
+        let mut mined = Vec::with_capacity(count);
+        for _ in 0..count {
+            self.check_producer(validator_address)?;
+            let mut transactions = Vec::new();
+            if self.config.block_reward > 0.0 {
+                transactions.push(Transaction::new(
+                    "0".to_string(),
+                    validator_address.to_string(),
+                    self.config.block_reward,
+                ));
+            }
+            let mut block = Block::new(
+                self.chain.len() as u32,
+                transactions,
+                self.get_latest_block().hash.clone(),
+                validator_address.to_string(),
+            );
+            block.sign(keypair.as_ref()).map_err(BlockchainError::SigningFailed)?;
+            self.apply_block(&block)?;
+            self.chain.push(block.clone());
+            self.record(ChainEvent::BlockAdded(block.clone()));
+            self.notify_watchers(&block);
+            mined.push(block);
+        }
+        Ok(mined)
+    }
+
+    /// The held keypair a validator signs its blocks with
+    fn producer_key(&self, validator_address: &str) -> Result<Arc<Keypair>, BlockchainError> {
+        self.keypairs
+            .get(validator_address)
+            .cloned()
+            .ok_or_else(|| BlockchainError::SigningFailed(format!("no keypair held for validator {}", validator_address)))
+    }
+
+    /// Whether the block carries a signature from the key behind its validator address
+    fn has_valid_producer_signature(&self, block: &Block) -> bool {
+        self.verifying_key(&block.validator)
+            .is_some_and(|public_key| block.verify_signature(&public_key))
+    }
+
+    /// Ensures the address may produce the next block
+    fn check_producer(&self, validator_address: &str) -> Result<(), BlockchainError> {
+        // Ensure validator authorization
+        if !self.is_validator(validator_address) {
+            return Err(BlockchainError::NotValidator);
+        }
+
+        // Ensure it is this validator's turn under the consensus mode
+        if let Some(producer) = self.next_block_producer() {
+            if producer != validator_address {
+                return Err(BlockchainError::NotProducerTurn { producer });
+            }
+        }
+        Ok(())
+    }
+
+    /// Assembles the block `create_block` would produce, without changing any state
+    ///
+    /// Transactions `pending_validity` rejects are left out.
+    pub fn preview_block(&self, validator_address: &str) -> Result<Block, BlockchainError> {
+        self.check_producer(validator_address)?;
+
+        // Ensure there are transactions to include
+        if self.pending_transactions.is_empty() {
+            return Err(BlockchainError::NoPendingTransactions);
+        }
+
+        let selected = self.select_valid_for_block();
+        if selected.is_empty() {
+            return Err(BlockchainError::NoValidTransactions);
+        }
+
+        // Pay the block reward to the validator ahead of the selected transactions
+        let mut transactions = Vec::new();
+        if self.config.block_reward > 0.0 {
+            transactions.push(Transaction::new(
+                "0".to_string(),
+                validator_address.to_string(),
+                self.config.block_reward,
+            ));
+        }
+        transactions.extend(selected);
+
+        Ok(Block::new(
+            self.chain.len() as u32,
+            transactions,
+            self.get_latest_block().hash.clone(),
+            validator_address.to_string(),
+        ))
+    }
+
+    /// The selected pending transactions that are still valid, as the next block would include them
+    fn select_valid_for_block(&self) -> Vec<Transaction> {
+        let valid: HashSet<String> = self
+            .pending_validity()
+            .into_iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(index, _)| self.pending_transactions[index].calculate_hash())
+            .collect();
+        self.select_for_block()
+            .into_iter()
+            .filter(|tx| valid.contains(&tx.calculate_hash()))
+            .collect()
+    }
+
+    /// What the producer of the next block would earn now: the selected fees plus the block reward
+    ///
+    /// Returns 0 when no valid transaction is pending, since no block can be created then.
+    pub fn next_block_revenue(&self) -> f64 {
+        let selected = self.select_valid_for_block();
+        if selected.is_empty() {
+            return 0.0;
+        }
+        selected.iter().map(|tx| tx.fee).sum::<f64>() + self.config.block_reward
+    }
+
+    /// Selects the pending transactions that fit into the next block, in canonical order
+    pub fn select_for_block(&self) -> Vec<Transaction> {
+        let limit = if self.config.max_block_transactions == 0 {
+            self.pending_transactions.len()
+        } else {
+            self.config.max_block_transactions
+        };
+        let mut candidates: Vec<Transaction> = self.selection_order().into_iter().take(limit).cloned().collect();
+        candidates.sort_by(canonical_order);
+        candidates
+    }
+
+    /// Pending transactions in the order blocks pick them
+    fn selection_order(&self) -> Vec<&Transaction> {
+        let mut candidates: Vec<&Transaction> = self.pending_transactions.iter().collect();
+        candidates.sort_by(|a, b| canonical_order(a, b));
+
+        // Validator transactions jump the queue, but only for selection: they were
+        // admitted through the same add_transaction checks as everyone else
+        if self.config.prioritize_validator_transactions {
+            candidates.sort_by_key(|tx| !self.is_validator(&tx.sender));
+        }
+        candidates
+    }
+
+    /// Pending transactions that would not be selected within the next `blocks_threshold` blocks
+    ///
+    /// Assumes no new transactions arrive; without a block size limit nothing is ever stuck.
+    pub fn stuck_transactions(&self, blocks_threshold: u32) -> Vec<&Transaction> {
+        if self.config.max_block_transactions == 0 {
+            return Vec::new();
+        }
+        let capacity = self.config.max_block_transactions.saturating_mul(blocks_threshold as usize);
+        self.selection_order().into_iter().skip(capacity).collect()
+    }
+
+    /// Fee a transaction would need to be selected within `blocks_threshold` blocks
+    ///
+    /// Returns None when no fee would help (validator transactions fill the space
+    /// ahead of it) or the fee would exceed the chain's maximum fee.
+    pub fn suggested_fee(&self, transaction: &Transaction, blocks_threshold: u32) -> Option<f64> {
+        let capacity = self.config.max_block_transactions.saturating_mul(blocks_threshold as usize);
+        let tx_hash = transaction.calculate_hash();
+        let others: Vec<&Transaction> = self
+            .selection_order()
+            .into_iter()
+            .filter(|tx| tx.calculate_hash() != tx_hash)
+            .collect();
+        if capacity == 0 || others.len() < capacity {
+            return Some(transaction.fee);
+        }
+
+        // Outbid the last transaction that still makes it in
+        let cutoff = others[capacity - 1];
+        let prioritized = |tx: &Transaction| self.config.prioritize_validator_transactions && self.is_validator(&tx.sender);
+        if prioritized(cutoff) && !prioritized(transaction) {
+            return None;
+        }
+        let fee = cutoff.fee + 0.01;
+        match self.config.max_fee {
+            Some(maximum) if fee > maximum => None,
+            _ => Some(fee),
+        }
+    }
+
+    /// Size of the next block when it falls below `min_block_transactions_warn`
+    pub fn small_block_size(&self) -> Option<usize> {
+        let count = self.select_for_block().len();
+        if count > 0 && count < self.config.min_block_transactions_warn {
+            Some(count)
+        } else {
+            None
+        }
+    }
+
+    /// Summarizes the pending pool and how much of it the next block would take
+    pub fn mempool_summary(&self) -> MempoolSummary {
+        MempoolSummary {
+            transaction_count: self.pending_transactions.len(),
+            total_value: self.pending_transactions.iter().map(|tx| tx.amount).sum(),
+            total_fees: self.pending_transactions.iter().map(|tx| tx.fee).sum(),
+            fits_in_next_block: self.select_for_block().len(),
+        }
+    }
+
+    /// Net pending change per account (incoming minus outgoing and fees), sorted by address
+    pub fn pending_deltas(&self) -> Vec<(String, f64)> {
+        let mut deltas: BTreeMap<String, f64> = BTreeMap::new();
+        for tx in &self.pending_transactions {
+            if tx.sender != "0" {
+                *deltas.entry(tx.sender.clone()).or_insert(0.0) -= tx.amount + tx.fee;
+            }
+            if !self.requires_claim(tx) {
+                *deltas.entry(tx.recipient.clone()).or_insert(0.0) += tx.amount;
+            }
+        }
+        deltas.into_iter().collect()
+    }
+
+    /// Pending transactions grouped by sender, each group in pool order
+    pub fn pending_by_sender(&self) -> HashMap<String, Vec<&Transaction>> {
+        let mut groups: HashMap<String, Vec<&Transaction>> = HashMap::new();
+        for tx in &self.pending_transactions {
+            groups.entry(tx.sender.clone()).or_default().push(tx);
+        }
+        groups
+    }
+
+    /// Removes one pending entry for each of the given transactions
+    fn remove_pending(&mut self, included: &[Transaction]) {
+        for tx in included {
+            let hash = tx.calculate_hash();
+            if let Some(position) = self
+                .pending_transactions
+                .iter()
+                .position(|pending| pending.calculate_hash() == hash)
+            {
+                self.pending_transactions.remove(position);
+            }
+        }
+    }
+
+    /// Applies a block's transactions to account balances
+    ///
+    /// Balances are only updated if every change stays representable; on overflow
+    /// the accounts are left untouched.
+    fn apply_block(&mut self, block: &Block) -> Result<(), BlockchainError> {
+        let (updated, claims) = self.stage_block(block)?;
+        for (address, balance) in &updated {
+            let low = self.balance_lows.entry(address.clone()).or_insert(*balance);
+            *low = low.min(*balance);
+        }
+        self.accounts.extend(updated);
+        self.pending_claims = claims;
+        self.index_block(block);
+        Ok(())
+    }
+
+    /// Computes the balances a block changes and the held transfers left after it
+    ///
+    /// Transfers above the claim threshold are held instead of credited, and held
+    /// transfers past their expiry return to the sender.
+    fn stage_block(&self, block: &Block) -> Result<(HashMap<String, f64>, Vec<PendingClaim>), BlockchainError> {
+        let expiry_blocks = self.config.claim_expiry_blocks;
+        let held: Vec<bool> = block.transactions.iter().map(|tx| self.requires_claim(tx)).collect();
+
+        let mut updated: HashMap<String, f64> = HashMap::new();
+        let mut credit = |address: &str, delta: f64| -> Result<(), BlockchainError> {
+            let balance = match updated.get(address) {
+                Some(balance) => *balance,
+                None => *self.accounts.get(address).unwrap_or(&0.0),
+            };
+            updated.insert(address.to_string(), checked_balance(address, balance, delta)?);
+            Ok(())
+        };
+
+        // Refund held transfers nobody claimed in time
+        let (expired, mut claims): (Vec<PendingClaim>, Vec<PendingClaim>) = self
+            .pending_claims
+            .iter()
+            .cloned()
+            .partition(|claim| block.index >= claim.block_index.saturating_add(expiry_blocks));
+        for claim in &expired {
+            credit(&claim.sender, claim.amount)?;
+        }
+
+        for (tx, held) in block.transactions.iter().zip(held) {
+            // Debit sender (except genesis)
+            if tx.sender != "0" {
+                credit(&tx.sender, -(tx.amount + tx.fee))?;
+            }
+            
+            // Credit recipient, or hold the transfer until it is claimed
+            if held {
+                claims.push(PendingClaim {
+                    tx_hash: tx.calculate_hash(),
+                    sender: tx.sender.clone(),
+                    recipient: tx.recipient.clone(),
+                    amount: tx.amount,
+                    block_index: block.index,
+                });
+            } else {
+                credit(&tx.recipient, tx.amount)?;
+            }
+
+            // Release a held transfer to the recipient claiming it
+            if let Some(claim_of) = &tx.claim_of {
+                let position = claims
+                    .iter()
+                    .position(|claim| &claim.tx_hash == claim_of && claim.recipient == tx.sender);
+                if let Some(position) = position {
+                    let claim = claims.remove(position);
+                    credit(&claim.recipient, claim.amount)?;
+                }
+            }
+
+            // Credit the fee to the block's validator
+            if tx.fee > 0.0 {
+                credit(&block.validator, tx.fee)?;
+            }
+        }
+
+        Ok((updated, claims))
+    }
+
+    /// Records a block's transactions in the address index
+    fn index_block(&mut self, block: &Block) {
+        for (position, tx) in block.transactions.iter().enumerate() {
+            let entry = (block.index, position);
+            if tx.sender != "0" {
+                self.address_index.entry(tx.sender.clone()).or_default().push(entry);
+            }
+            if tx.recipient != tx.sender {
+                self.address_index.entry(tx.recipient.clone()).or_default().push(entry);
+            }
+        }
+    }
+
+    /// Rebuilds the address index from scratch by scanning the chain
+    fn rebuild_address_index(&mut self) {
+        self.address_index.clear();
+        for block in self.chain.clone() {
+            self.index_block(&block);
+        }
+
+        // Past balances are not stored, so the lows come from a single replay
+        let mut replay = Blockchain::empty(self.config.clone());
+        for block in &self.chain {
+            if replay.apply_block(block).is_err() {
+                break;
+            }
+        }
+        self.balance_lows = replay.balance_lows;
+    }
+
+    /// Returns the confirmed transactions involving an address, in chain order
+    fn indexed_transactions(&self, address: &str) -> impl Iterator<Item = (u32, &Transaction)> {
+        self.address_index
+            .get(address)
+            .into_iter()
+            .flatten()
+            .map(|(block_index, position)| {
+                (*block_index, &self.chain[*block_index as usize].transactions[*position])
+            })
+    }
+
+    /// Registers a callback fired once the transaction with the given hash is mined
+    pub fn watch_transaction(&mut self, tx_hash: &str, callback: TransactionCallback) {
+        self.transaction_watchers
+            .entry(tx_hash.to_string())
+            .or_default()
+            .push(callback);
+    }
+
+    /// Invokes and removes the watchers for every transaction confirmed by the block
+    fn notify_watchers(&mut self, block: &Block) {
+        if self.transaction_watchers.is_empty() {
+            return;
+        }
+
+        for tx in &block.transactions {
+            if let Some(callbacks) = self.transaction_watchers.remove(&tx.calculate_hash()) {
+                for mut callback in callbacks {
+                    callback(tx, block);
+                }
+            }
+        }
+    }
+
+    /// Validates the entire blockchain
+    pub fn validate_chain(&self) -> bool {
+        self.validate_chain_detailed().is_ok()
+    }
+
+    /// Validates the entire blockchain, naming the first block that fails and why
+    pub fn validate_chain_detailed(&self) -> Result<(), ValidationFailure> {
+        self.validate_chain_with_progress(&mut |_, _| {})
+    }
+
+    /// Validates the entire blockchain, reporting (validated, total) blocks as it goes
+    pub fn validate_chain_with_progress(&self, progress: &mut dyn FnMut(usize, usize)) -> Result<(), ValidationFailure> {
+        self.validate_blocks(&self.chain, progress)?;
+        if !self.recorded_balance_mismatches().is_empty() {
+            return Err(ValidationFailure {
+                index: self.get_latest_block().index,
+                kind: FailureKind::RecordedBalance,
+            });
+        }
+        Ok(())
+    }
+
+    /// Addresses whose recorded balance differs from replaying the chain from genesis
+    ///
+    /// Catches a genesis block or accounts map edited after the fact.
+    pub fn recorded_balance_mismatches(&self) -> Vec<String> {
+        let mut replay = Blockchain::empty(self.config.clone());
+        for block in &self.chain {
+            if replay.apply_block(block).is_err() {
+                break;
+            }
+        }
+
+        let addresses: BTreeSet<&String> = self.accounts.keys().chain(replay.accounts.keys()).collect();
+        addresses
+            .into_iter()
+            .filter(|address| self.get_account_balance(address) != replay.get_account_balance(address))
+            .cloned()
+            .collect()
+    }
+
+    /// Validates the chain, also failing on soft warnings that lenient validation tolerates
+    pub fn validate_chain_strict(&self) -> bool {
+        self.validate_chain() && self.validation_warnings().is_empty()
+    }
+
+    /// Lists soft issues that don't invalidate the chain under lenient validation
+    pub fn validation_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for i in 1..self.chain.len() {
+            let current_block = &self.chain[i];
+            let previous_block = &self.chain[i - 1];
+
+            if current_block.timestamp < previous_block.timestamp {
+                warnings.push(format!(
+                    "Block #{} has a timestamp earlier than its predecessor",
+                    current_block.index
+                ));
+            }
+
+            if self.public_key_for(&current_block.validator).is_none() {
+                warnings.push(format!(
+                    "Block #{} validator {} has no known public key",
+                    current_block.index, current_block.validator
+                ));
+            }
+        }
+
+        for block in self.chain.iter().skip(1).filter(|block| block.validator_signature.is_none()) {
+            warnings.push(format!("Block #{} is not signed by its validator", block.index));
+        }
+
+        for address in self.genesis_key_mismatches() {
+            warnings.push(format!(
+                "Genesis address {} is registered with a key that does not match it",
+                address
+            ));
+        }
+        warnings
+    }
+
+    /// Validates a sequence of blocks against this chain's validator set
+    ///
+    /// Balances are replayed from scratch alongside, so overdrafts and overflows are
+    /// caught at the block that causes them.
+    fn validate_blocks(&self, blocks: &[Block], progress: &mut dyn FnMut(usize, usize)) -> Result<(), ValidationFailure> {
+        // Empty chain is valid
+        if blocks.is_empty() {
+            return Ok(());
+        }
+
+        // Signatures are checked up front on all cores; the result is consumed in block order
+        #[cfg(feature = "parallel")]
+        let invalid_signature = self.first_invalid_signature(blocks);
+
+        // The genesis block has no predecessor, but its contents must still match its hash
+        if blocks[0].hash != blocks[0].calculate_hash() {
+            return Err(ValidationFailure { index: blocks[0].index, kind: FailureKind::Hash });
+        }
+
+        let mut replay = Blockchain::empty(self.config.clone());
+        replay.replay_block(&blocks[0])?;
+        progress(1, blocks.len());
+        
+        // Validate each block starting from the second one
+        for i in 1..blocks.len() {
+            let current_block = &blocks[i];
+            let previous_block = &blocks[i - 1];
+            let fail = |kind| ValidationFailure { index: current_block.index, kind };
+
+            check_block_structure(current_block, previous_block).map_err(fail)?;
+            check_coinbase(current_block, self.config.block_reward).map_err(fail)?;
+
+            // Validate all transactions in the block
+            #[cfg(feature = "parallel")]
+            let signatures_valid = invalid_signature != Some(i);
+            #[cfg(not(feature = "parallel"))]
+            let signatures_valid = current_block.transactions.iter().all(|tx| {
+                let verifying_key = self.verifying_key(&tx.sender);
+                let mut stdout = io::stdout();
+                let log = self.verbose_validation.then_some(&mut stdout as &mut dyn Write);
+                tx.is_valid(verifying_key.as_ref().map(|key| key as &dyn Verifier), log)
+            });
+            if !signatures_valid {
+                return Err(fail(FailureKind::Signature));
+            }
+
+            // Check if the block was created by a current or retired validator
+            if !self.validators.contains_key(&current_block.validator) {
+                return Err(fail(FailureKind::Validator));
+            }
+
+            // Blocks from before headers were signed carry no signature; see validation_warnings
+            if current_block.validator_signature.is_some() && !self.has_valid_producer_signature(current_block) {
+                return Err(fail(FailureKind::ProducerSignature));
+            }
+
+            replay.replay_block(current_block)?;
+            progress(i + 1, blocks.len());
+        }
+
+        Ok(())
+    }
+
+    /// Position of the first block after genesis holding a transaction that fails verification
+    #[cfg(feature = "parallel")]
+    fn first_invalid_signature(&self, blocks: &[Block]) -> Option<usize> {
+        use rayon::prelude::*;
+
+        // Resolve keys up front: the chain itself is not shared across threads
+        let keys: HashMap<&str, Option<PublicKey>> = blocks
+            .iter()
+            .skip(1)
+            .flat_map(|block| &block.transactions)
+            .map(|tx| (tx.sender.as_str(), self.verifying_key(&tx.sender)))
+            .collect();
+        let verbose = self.verbose_validation;
+
+        blocks
+            .par_iter()
+            .enumerate()
+            .skip(1)
+            .find_first(|(_, block)| {
+                block.transactions.iter().any(|tx| {
+                    let verifying_key = keys.get(tx.sender.as_str()).and_then(Option::as_ref);
+                    let mut stdout = io::stdout();
+                    let log = verbose.then_some(&mut stdout as &mut dyn Write);
+                    !tx.is_valid(verifying_key.map(|key| key as &dyn Verifier), log)
+                })
+            })
+            .map(|(position, _)| position)
+    }
+
+    /// Applies a block during validation, failing if any balance overflows or goes negative
+    fn replay_block(&mut self, block: &Block) -> Result<(), ValidationFailure> {
+        let fail = ValidationFailure { index: block.index, kind: FailureKind::Balance };
+        self.apply_block(block).map_err(|_| fail.clone())?;
+        if self.accounts.values().any(|balance| *balance < 0.0) {
+            return Err(fail);
+        }
+        Ok(())
+    }
+
+    /// Lists every break in the chain instead of stopping at the first one
+    pub fn diagnose(&self) -> Vec<ChainProblem> {
+        let mut problems = Vec::new();
+
+        for pair in self.chain.windows(2) {
+            let (previous_block, current_block) = (&pair[0], &pair[1]);
+            let index = current_block.index;
+
+            let expected_hash = current_block.calculate_hash();
+            if current_block.hash != expected_hash {
+                problems.push(ChainProblem::HashMismatch {
+                    index,
+                    expected: expected_hash,
+                    actual: current_block.hash.clone(),
+                });
+            }
+
+            if current_block.previous_hash != previous_block.hash {
+                problems.push(ChainProblem::BrokenLink {
+                    index,
+                    expected: previous_block.hash.clone(),
+                    actual: current_block.previous_hash.clone(),
+                });
+            }
+
+            let user_transactions: Vec<&Transaction> = current_block
+                .transactions
+                .iter()
+                .filter(|tx| tx.sender != "0")
+                .collect();
+            if user_transactions
+                .windows(2)
+                .any(|pair| canonical_order(pair[0], pair[1]) == Ordering::Greater)
+            {
+                problems.push(ChainProblem::TransactionOrder { index });
+            }
+
+            for tx in &current_block.transactions {
+                if !self.has_valid_signature(tx) {
+                    problems.push(ChainProblem::InvalidTransaction {
+                        index,
+                        tx_hash: tx.calculate_hash(),
+                    });
+                }
+            }
+
+            if !self.validators.contains_key(&current_block.validator) {
+                problems.push(ChainProblem::UnknownValidator {
+                    index,
+                    validator: current_block.validator.clone(),
+                });
+            }
+        }
+
+        problems
+    }
+
+    /// Replaces the chain with a longer valid candidate, returning whether it was replaced
+    pub fn try_replace_chain(&mut self, candidate: Vec<Block>) -> Result<bool, String> {
+        if candidate.len() <= self.chain.len() {
+            return Ok(false);
+        }
+
+        if let Err(failure) = self.validate_blocks(&candidate, &mut |_, _| {}) {
+            return Err(format!("Candidate chain is invalid: {}", failure));
+        }
+
+        // Find where the candidate diverges from the local chain
+        let common = self
+            .chain
+            .iter()
+            .zip(candidate.iter())
+            .take_while(|(local, remote)| local.hash == remote.hash)
+            .count();
+
+        if common == 0 {
+            return Err("Candidate chain has a different genesis block".to_string());
+        }
+
+        let reorg_depth = self.chain.len() - common;
+        println!("Attempted reorg depth: {}", reorg_depth);
+        if let Some(max_depth) = self.config.max_reorg_depth {
+            if reorg_depth > max_depth {
+                return Err(format!(
+                    "Reorg depth {} exceeds the maximum of {}",
+                    reorg_depth, max_depth
+                ));
+            }
+        }
+
+        let previous_chain = std::mem::replace(&mut self.chain, candidate);
+        if let Err(e) = self.rebuild_accounts() {
+            // Balances replayed fine before, so restoring the old chain cannot fail
+            self.chain = previous_chain;
+            self.rebuild_accounts().map_err(|e| e.to_string())?;
+            return Err(format!("Candidate chain rejected: {}", e));
+        }
+
+        // Drop pending transactions that the new chain already confirms
+        let confirmed: Vec<Transaction> = self.chain[common..]
+            .iter()
+            .flat_map(|block| block.transactions.clone())
+            .collect();
+        self.remove_pending(&confirmed);
+        self.record(ChainEvent::ChainReplaced(self.chain.clone()));
+
+        Ok(true)
+    }
+
+    /// Recomputes every block hash and previous-hash link under the current hashing rules
+    ///
+    /// For development and format migrations only: this rewrites history and makes any
+    /// chain pass the hash checks. Transaction signatures are left untouched; block
+    /// signatures are redone for validators whose keys are held and dropped otherwise.
+    pub fn rehash_chain(&mut self) {
+        let mut previous_hash: Option<String> = None;
+        for block in self.chain.iter_mut() {
+            if let Some(previous_hash) = previous_hash {
+                block.previous_hash = previous_hash;
+            }
+            block.hash = block.calculate_hash();
+            previous_hash = Some(block.hash.clone());
+
+            // A new hash voids the old signature; re-sign where this node holds the key
+            block.validator_signature = None;
+            if block.index > 0 {
+                if let Some(keypair) = self.keypairs.get(&block.validator) {
+                    let _ = block.sign(keypair.as_ref());
+                }
+            }
+        }
+        self.record(ChainEvent::ChainRehashed);
+    }
+
+    /// Recomputes every account balance by replaying the chain
+    fn rebuild_accounts(&mut self) -> Result<(), BlockchainError> {
+        for balance in self.accounts.values_mut() {
+            *balance = 0.0;
+        }
+        self.pending_claims.clear();
+        self.address_index.clear();
+        self.balance_lows.clear();
+        for block in self.chain.clone() {
+            self.apply_block(&block)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the total block rewards and fees earned by a validator
+    pub fn validator_earnings(&self, address: &str) -> (f64, f64) {
+        let mut rewards = 0.0;
+        let mut fees = 0.0;
+
+        // The genesis block's allocation is not a reward
+        for block in self.chain.iter().skip(1).filter(|block| block.validator == address) {
+            for tx in &block.transactions {
+                if tx.sender == "0" && tx.recipient == address {
+                    rewards += tx.amount;
+                }
+                fees += tx.fee;
+            }
+        }
+        (rewards, fees)
+    }
+
+    /// Returns the highest block index in which the address sent or received funds
+    pub fn last_activity(&self, address: &str) -> Option<u32> {
+        self.indexed_transactions(address).last().map(|(block_index, _)| block_index)
+    }
+
+    /// Drops zero-balance accounts idle for more than `min_idle_blocks`, returning how many
+    ///
+    /// Accounts with a held keypair, a validator entry, a pending transaction or an
+    /// unclaimed transfer are kept. Replaying the chain recreates pruned entries.
+    pub fn prune_inactive_accounts(&mut self, min_idle_blocks: u32) -> usize {
+        let height = self.get_latest_block().index;
+        let prunable: Vec<String> = self
+            .accounts
+            .iter()
+            .filter(|(address, balance)| {
+                **balance == 0.0
+                    && !self.keypairs.contains_key(*address)
+                    && !self.validators.contains_key(*address)
+                    && !self
+                        .pending_transactions
+                        .iter()
+                        .any(|tx| &tx.sender == *address || &tx.recipient == *address)
+                    && !self
+                        .pending_claims
+                        .iter()
+                        .any(|claim| &claim.sender == *address || &claim.recipient == *address)
+                    && self
+                        .last_activity(address)
+                        .filter(|last| height - last <= min_idle_blocks)
+                        .is_none()
+            })
+            .map(|(address, _)| address.clone())
+            .collect();
+
+        for address in &prunable {
+            self.accounts.remove(address);
+        }
+        let pruned = prunable.len();
+        if pruned > 0 {
+            self.record(ChainEvent::AccountsPruned(prunable));
+        }
+        pruned
+    }
+
+    /// Builds a receipt for a confirmed transaction
+    pub fn receipt(&self, tx_hash: &str) -> Option<Receipt> {
+        let tip = self.get_latest_block().index;
+        self.chain.iter().rev().find_map(|block| {
+            block
+                .transactions
+                .iter()
+                .position(|tx| tx.calculate_hash() == tx_hash)
+                .map(|position| {
+                    let transaction = block.transactions[position].clone();
+                    Receipt {
+                        fee: transaction.fee,
+                        transaction,
+                        tx_hash: tx_hash.to_string(),
+                        block_index: block.index,
+                        block_hash: block.hash.clone(),
+                        position,
+                        confirmations: tip - block.index + 1,
+                    }
+                })
+        })
+    }
+
+    /// Confirmed transactions with a timestamp in `start_ts..=end_ts`, with their block indices
+    pub fn transactions_between(&self, start_ts: u64, end_ts: u64) -> Vec<(u32, &Transaction)> {
+        self.chain
+            .iter()
+            .flat_map(|block| block.transactions.iter().map(move |tx| (block.index, tx)))
+            .filter(|(_, tx)| tx.timestamp >= start_ts && tx.timestamp <= end_ts)
+            .collect()
+    }
+
+    /// Blocks with a timestamp in `start_ts..=end_ts`, in chain order
+    pub fn blocks_between(&self, start_ts: u64, end_ts: u64) -> Vec<&Block> {
+        self.chain
+            .iter()
+            .filter(|block| block.timestamp >= start_ts && block.timestamp <= end_ts)
+            .collect()
+    }
+
+    /// Aggregates user transactions into fixed windows by block timestamp, oldest first
+    ///
+    /// Windows without activity are included with zero counts; minting transactions are skipped.
+    /// Genesis holds only allocations and may carry any timestamp (often 0), so the windows
+    /// start at the first block after it. At most `MAX_TIMESERIES_BUCKETS` of the most
+    /// recent windows are returned.
+    pub fn timeseries(&self, bucket_seconds: u64) -> Vec<TimeBucket> {
+        let blocks = self.chain.get(1..).unwrap_or_default();
+        if bucket_seconds == 0 || blocks.is_empty() {
+            return Vec::new();
+        }
+
+        let first = blocks.iter().map(|block| block.timestamp).min().unwrap_or(0);
+        let last = blocks.iter().map(|block| block.timestamp).max().unwrap_or(0);
+        let last_start = last - last % bucket_seconds;
+        let first_start = if last_start.saturating_sub(first) / bucket_seconds >= MAX_TIMESERIES_BUCKETS {
+            last_start - (MAX_TIMESERIES_BUCKETS - 1) * bucket_seconds
+        } else {
+            first - first % bucket_seconds
+        };
+        let bucket_count = ((last - first_start) / bucket_seconds + 1) as usize;
+
+        let mut buckets: Vec<TimeBucket> = (0..bucket_count)
+            .map(|i| TimeBucket {
+                start: first_start + i as u64 * bucket_seconds,
+                transaction_count: 0,
+                volume: 0.0,
+            })
+            .collect();
+
+        for block in blocks.iter().filter(|block| block.timestamp >= first_start) {
+            let bucket = &mut buckets[((block.timestamp - first_start) / bucket_seconds) as usize];
+            for tx in block.transactions.iter().filter(|tx| tx.sender != "0") {
+                bucket.transaction_count += 1;
+                bucket.volume += tx.amount;
+            }
+        }
+        buckets
+    }
+
+    /// Height of the most recent block the address produced
+    pub fn last_produced(&self, address: &str) -> Option<u32> {
+        self.chain
+            .iter()
+            .rev()
+            .find(|block| block.validator == address)
+            .map(|block| block.index)
+    }
+
+    /// Active validators that have not produced a block in the last `max_idle_blocks` blocks
+    ///
+    /// Validators that never produced are measured from genesis.
+    pub fn inactive_validators(&self, max_idle_blocks: u32) -> Vec<String> {
+        let height = self.get_latest_block().index;
+        self.active_validators()
+            .into_iter()
+            .filter(|validator| {
+                let last = self.last_produced(validator).unwrap_or(0);
+                height - last > max_idle_blocks
+            })
+            .collect()
+    }
+
+    /// Collects sent/received counts and volumes for an account from the address index
+    pub fn account_stats(&self, address: &str) -> AccountStats {
+        let mut stats = AccountStats {
+            min_balance: self.min_balance_history(address),
+            ..AccountStats::default()
+        };
+        for (block_index, tx) in self.indexed_transactions(address) {
+            if stats.first_seen.is_none() {
+                stats.first_seen = Some(block_index);
+            }
+            if tx.sender == address {
+                stats.sent_count += 1;
+                stats.volume_sent += tx.amount;
+            }
+            if tx.recipient == address {
+                stats.received_count += 1;
+                stats.volume_received += tx.amount;
+            }
+        }
+        stats
+    }
+
+    /// Lowest balance the account held after any block
+    ///
+    /// Blocks before the account first appears are ignored, so an account created
+    /// mid-chain is not reported as having held nothing. Returns 0 for unknown accounts.
+    pub fn min_balance_history(&self, address: &str) -> f64 {
+        self.balance_lows.get(address).copied().unwrap_or(0.0)
+    }
+
+    /// Each address this account exchanged coins with, with the transaction count and volume, sorted by address
+    ///
+    /// Self-transfers and coins minted by blocks are left out.
+    pub fn counterparties(&self, address: &str) -> Vec<(String, usize, f64)> {
+        let mut totals: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+        for (_, tx) in self.indexed_transactions(address) {
+            let counterparty = if tx.sender == address { &tx.recipient } else { &tx.sender };
+            if counterparty == address || counterparty == "0" {
+                continue;
+            }
+            let entry = totals.entry(counterparty.clone()).or_insert((0, 0.0));
+            entry.0 += 1;
+            entry.1 += tx.amount;
+        }
+        totals
+            .into_iter()
+            .map(|(counterparty, (count, volume))| (counterparty, count, volume))
+            .collect()
+    }
+
+    /// Measures the saved chain size and projects it to the given height
+    pub fn storage_report(&self, target_height: u32) -> StorageReport {
+        // Same serialization as save_to_file so the figure matches the file on disk
+        let current_size = serde_json::to_string_pretty(self)
+            .map(|json| json.len())
+            .unwrap_or_default();
+        let average_block_size = if self.chain.is_empty() {
+            0.0
+        } else {
+            current_size as f64 / self.chain.len() as f64
+        };
+        let remaining_blocks = (target_height as f64 - self.chain.len() as f64).max(0.0);
+
+        StorageReport {
+            current_size,
+            average_block_size,
+            target_height,
+            projected_size: current_size as f64 + remaining_blocks * average_block_size,
+        }
+    }
+
+    /// Returns every address that has sent or received a confirmed transaction
+    pub fn participants(&self) -> HashSet<String> {
+        self.address_index
+            .keys()
+            .filter(|address| address.as_str() != "0")
+            .cloned()
+            .collect()
+    }
+
+    /// Lists held keys whose addresses have no balance entry and never transacted
+    pub fn orphaned_keypairs(&self) -> Vec<String> {
+        let mut orphans: Vec<String> = self
+            .keypairs
+            .keys()
+            .chain(self.public_keys.keys())
+            .filter(|address| {
+                !self.accounts.contains_key(*address) && self.last_activity(address).is_none()
+            })
+            .cloned()
+            .collect();
+        orphans.sort();
+        orphans.dedup();
+        orphans
+    }
+
+    /// Compares the held keys against the chain's addresses
+    pub fn reconcile_accounts(&self) -> AccountReconciliation {
+        let mut chain_addresses: HashSet<String> = self.participants();
+        chain_addresses.extend(self.accounts.keys().cloned());
+        chain_addresses.extend(self.validators.keys().cloned());
+        chain_addresses.remove("0");
+
+        let mut missing_keypairs: Vec<String> = chain_addresses
+            .into_iter()
+            .filter(|address| !self.keypairs.contains_key(address))
+            .collect();
+        missing_keypairs.sort();
+
+        let mut derivation_mismatches: Vec<String> = self
+            .keypairs
+            .keys()
+            .chain(self.public_keys.keys())
+            .filter(|address| match self.public_key_for(address) {
+                Some(public_key) => public_key.address() != **address,
+                None => false,
+            })
+            .cloned()
+            .collect();
+        derivation_mismatches.sort();
+        derivation_mismatches.dedup();
+
+        AccountReconciliation {
+            missing_keypairs,
+            orphaned_keypairs: self.orphaned_keypairs(),
+            derivation_mismatches,
+        }
+    }
+
+    /// Hashes all account balances, sorted by address, into a single digest
+    pub fn state_root(&self) -> String {
+        let sorted: BTreeMap<&String, &f64> = self.accounts.iter().collect();
+        let canonical = serde_json::to_string(&sorted).unwrap_or_default();
+        let mut hasher = Sha256::new();
+        hasher.update(canonical.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Digest of the ordered block hashes, for comparing chains between nodes in one step
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for block in &self.chain {
+            hasher.update(block.hash.as_bytes());
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Starts recording changes after the given events, beginning with a snapshot of the current state
+    pub fn record_events(&mut self, mut events: Vec<ChainEvent>) -> Result<(), String> {
+        let state = serde_json::to_value(&*self).map_err(|e| format!("Failed to serialize: {}", e))?;
+        events.push(ChainEvent::Started(Box::new(state)));
+        self.event_log = Some(events);
+        Ok(())
+    }
+
+    /// Changes recorded so far, or None when not recording
+    pub fn event_log(&self) -> Option<&[ChainEvent]> {
+        self.event_log.as_deref()
+    }
+
+    /// Stops recording and hands back the events recorded so far
+    pub fn take_event_log(&mut self) -> Option<Vec<ChainEvent>> {
+        self.event_log.take()
+    }
+
+    fn record(&mut self, event: ChainEvent) {
+        if let Some(events) = &mut self.event_log {
+            events.push(event);
+        }
+    }
+
+    /// Rebuilds a chain by applying a recorded event log from its first `Started` snapshot
+    ///
+    /// Every event goes through the same checks it passed when recorded, so a log that
+    /// was edited or recorded against a different build fails at the first divergence.
+    pub fn replay(events: &[ChainEvent]) -> Result<Blockchain, String> {
+        let mut blockchain: Option<Blockchain> = None;
+        for (position, event) in events.iter().enumerate() {
+            let fail = |e: String| format!("Event {} failed to replay: {}", position + 1, e);
+
+            if let ChainEvent::Started(state) = event {
+                let mut started: Blockchain = serde_json::from_value(state.as_ref().clone())
+                    .map_err(|e| fail(format!("invalid snapshot: {}", e)))?;
+                started.rebuild_address_index();
+                started.restore_public_keys();
+                blockchain = Some(started);
+                continue;
+            }
+            let chain = blockchain
+                .as_mut()
+                .ok_or_else(|| "Event log does not start with a snapshot".to_string())?;
+
+            match event {
+                ChainEvent::Started(_) => unreachable!("handled above"),
+                // Every recorded transaction was signed, so it must pass as a signed submission
+                ChainEvent::TransactionAdded(tx) => {
+                    chain.submit_signed_transaction(tx.clone()).map_err(|e| fail(e.to_string()))?
+                }
+                ChainEvent::PendingDropped(txs) => chain.remove_pending(txs),
+                ChainEvent::BlockAdded(block) => chain.append_block(block.clone()).map_err(|e| fail(e.to_string()))?,
+                ChainEvent::ChainReplaced(blocks) => {
+                    if !chain.try_replace_chain(blocks.clone()).map_err(fail)? {
+                        return Err(fail("replacement chain is not longer".to_string()));
+                    }
+                }
+                ChainEvent::ChainRehashed => chain.rehash_chain(),
+                ChainEvent::AccountRegistered(address) => {
+                    let public_key = chain
+                        .verifying_key(address)
+                        .ok_or_else(|| fail(format!("{} is not a public key", address)))?;
+                    chain.public_keys.insert(address.clone(), public_key);
+                    chain.accounts.entry(address.clone()).or_insert(0.0);
+                }
+                ChainEvent::AccountsPruned(addresses) => {
+                    for address in addresses {
+                        chain.accounts.remove(address);
+                    }
+                }
+                ChainEvent::ValidatorAdded(address) => chain.add_validator(address.clone()).map_err(fail)?,
+                ChainEvent::ValidatorTransferred { from, to } => chain.transfer_validator_role(from, to).map_err(fail)?,
+                ChainEvent::ConfigChanged(config) => chain.set_config(config.clone()).map_err(fail)?,
+            }
+        }
+        blockchain.ok_or_else(|| "Event log is empty".to_string())
+    }
+
+    /// Registers the public key behind every account address, as snapshots carry no key material
+    fn restore_public_keys(&mut self) {
+        let addresses: Vec<String> = self.accounts.keys().cloned().collect();
+        for address in addresses {
+            if let Some(public_key) = self.verifying_key(&address) {
+                self.public_keys.insert(address, public_key);
+            }
+        }
+    }
+
+    /// Gets the balance for an account
+    pub fn get_account_balance(&self, address: &str) -> f64 {
+        *self.accounts.get(address).unwrap_or(&0.0)
+    }
+    
+    /// Block rewards credited to the address that are still within `coinbase_maturity`
+    pub fn immature_rewards(&self, address: &str) -> f64 {
+        let height = self.get_latest_block().index;
+        self.indexed_transactions(address)
+            .filter(|(block_index, tx)| {
+                // Genesis allocations are minted too, but are not rewards
+                *block_index > 0
+                    && tx.sender == "0"
+                    && tx.recipient == address
+                    && height < block_index.saturating_add(self.config.coinbase_maturity)
+            })
+            .map(|(_, tx)| tx.amount)
+            .sum()
+    }
+
+    /// The balance the address may spend now, excluding immature rewards
+    pub fn spendable_balance(&self, address: &str) -> f64 {
+        self.get_account_balance(address) - self.immature_rewards(address)
+    }
+
+    /// Saves the blockchain and accounts files, replacing both or neither
+    pub fn save_to_file(&self, filename: &str, accounts_file: &str) -> Result<(), String> {
+        let blockchain_json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+    
+        let mut files = vec![(filename.to_string(), blockchain_json)];
+        files.extend(self.accounts_files(accounts_file)?);
+        replace_files(&files, &self.save_retry)
+    }
+
+    /// Streams the chain as newline-delimited JSON, one block per line, returning the block count
+    pub fn export_ndjson(&self, mut writer: impl Write) -> Result<usize, String> {
+        for block in &self.chain {
+            serde_json::to_writer(&mut writer, block).map_err(|e| format!("Failed to serialize block: {}", e))?;
+            writer.write_all(b"\n").map_err(|e| format!("Failed to write block: {}", e))?;
+        }
+        writer.flush().map_err(|e| format!("Failed to write block: {}", e))?;
+        Ok(self.chain.len())
+    }
+
+    /// Reads blocks written by `export_ndjson` one line at a time, appending those beyond the tip
+    ///
+    /// Blocks this chain already has must match it; new ones go through `append_block`.
+    /// Returns the number of blocks appended.
+    pub fn import_ndjson(&mut self, reader: impl BufRead) -> Result<usize, String> {
+        let mut appended = 0;
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| format!("Failed to read line {}: {}", line_number + 1, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let block: Block = serde_json::from_str(&line)
+                .map_err(|e| format!("Failed to parse block on line {}: {}", line_number + 1, e))?;
+
+            match self.chain.get(block.index as usize) {
+                Some(existing) if existing.hash == block.hash => {}
+                Some(_) => return Err(format!("Block #{} does not match this chain", block.index)),
+                None => {
+                    self.append_block(block).map_err(|e| e.to_string())?;
+                    appended += 1;
+                }
+            }
+        }
+        Ok(appended)
+    }
+
+    /// Applies a change and persists the result, or leaves memory as it was if either step fails
+    ///
+    /// A failed save leaves the previous chain and accounts files in place (see `save_to_file`).
+    pub fn commit<T>(
+        &mut self,
+        blockchain_file: &str,
+        accounts_file: &str,
+        change: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let snapshot = serde_json::to_string(self).map_err(|e| format!("Failed to serialize: {}", e))?;
+        let keypairs = self.keypairs.clone();
+        let public_keys = self.public_keys.clone();
+        let seen_transactions = self.seen_transactions.clone();
+        let recorded = self.event_log.as_ref().map_or(0, Vec::len);
+
+        let result = change(self).and_then(|value| {
+            self.save_to_file(blockchain_file, accounts_file)?;
+            Ok(value)
+        });
+
+        if result.is_err() {
+            let mut restored: Blockchain = serde_json::from_str(&snapshot)
+                .map_err(|e| format!("Failed to restore state: {}", e))?;
+            restored.keypairs = keypairs;
+            restored.public_keys = public_keys;
+            restored.seen_transactions = seen_transactions;
+            restored.accounts_passphrase = self.accounts_passphrase.take();
+            restored.save_retry = self.save_retry;
+            restored.dev_mode = self.dev_mode;
+            restored.verbose_validation = self.verbose_validation;
+            // Changes that were rolled back never happened as far as the log is concerned
+            restored.event_log = self.event_log.take().map(|mut events| {
+                events.truncate(recorded);
+                events
+            });
+            restored.transaction_watchers = std::mem::take(&mut self.transaction_watchers);
+            restored.rebuild_address_index();
+            *self = restored;
+        }
+        result
+    }
+
+    /// Encodes every held keypair in the accounts file format (address -> "secret:public")
+    pub fn encoded_keypairs(&self) -> HashMap<String, String> {
+    // การบันทึก keypair (public + secret key ทั้งหมดในรูปแบบ hex)
+        self.keypairs.iter()
+            .map(|(address, keypair)| {
+                let secret_hex = hex::encode(keypair.secret.as_bytes()); // แปลง secret key เป็น hex
+                let public_hex = hex::encode(keypair.public.as_bytes()); // แปลง public key เป็น hex
+                (address.clone(), format!("{}:{}", secret_hex, public_hex)) // เก็บไว้ในรูปแบบ secret:public
+            })
+            .collect()
+    }
+
+    /// Writes the in-memory keypairs to the accounts file
+    pub fn save_accounts_to_file(&self, accounts_file: &str) -> Result<(), String> {
+        replace_files(&self.accounts_files(accounts_file)?, &self.save_retry)
+    }
+
+    /// The accounts file's contents and, when a passphrase is set, its signature sidecar
+    fn accounts_files(&self, accounts_file: &str) -> Result<Vec<(String, String)>, String> {
+        let accounts_json = self.encoded_keypairs();
+        
+        // Serialize the accounts data into a pretty JSON format
+        let pretty_json = serde_json::to_string_pretty(&accounts_json)
+            .map_err(|e| format!("Failed to serialize accounts: {}", e))?;
+
+        let signature = self
+            .accounts_passphrase
+            .as_ref()
+            .map(|passphrase| integrity::accounts_signature(pretty_json.as_bytes(), passphrase));
+        let mut files = vec![(accounts_file.to_string(), pretty_json)];
+        if let Some(signature) = signature {
+            files.push((integrity::signature_path(accounts_file), signature));
+        }
+        Ok(files)
+    }
+    
+    
+    /// Loads only the blockchain file, without any signing keys
+    pub fn load_read_only(blockchain_file: &str) -> Result<Self, String> {
+        let blockchain_data = fs::read_to_string(blockchain_file)
+            .map_err(|_| format!("Failed to read blockchain file: {}", blockchain_file))?;
+        let mut blockchain: Blockchain = serde_json::from_str(&blockchain_data)
+            .map_err(|_| "Failed to parse blockchain file".to_string())?;
+
+        blockchain.rebuild_address_index();
+        Ok(blockchain)
+    }
+
+    /// Loads the blockchain with its keys; with a passphrase the accounts file must match its signature
+    pub fn load_from_file(blockchain_file: &str, accounts_file: &str, passphrase: Option<&str>) -> Result<Self, String> {
+        Self::load_from_file_with_progress(blockchain_file, accounts_file, passphrase, &mut |_, _| {})
+    }
+
+    /// Loads the blockchain and validates it, reporting (validated, total) blocks as it goes
+    ///
+    /// A chain that fails validation still loads, with a warning naming the first bad block.
+    /// An accounts file modified since it was signed with the passphrase is rejected, and
+    /// later saves sign it again.
+    pub fn load_from_file_with_progress(
+        blockchain_file: &str,
+        accounts_file: &str,
+        passphrase: Option<&str>,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<Self, String> {
+        if let Some(passphrase) = passphrase {
+            integrity::verify_accounts_file(accounts_file, passphrase, false)?;
+        }
+        let mut blockchain = Self::load_read_only(blockchain_file)?;
+        blockchain.accounts_passphrase = passphrase.map(str::to_string);
+    
+        let accounts_data = fs::read_to_string(accounts_file)
+            .map_err(|_| format!("Failed to read accounts file: {}", accounts_file))?;
+        let accounts: HashMap<String, String> = serde_json::from_str(&accounts_data)
+            .map_err(|_| "Failed to parse accounts file".to_string())?;
+    
+        for (address, keypair_str) in accounts {
+            let keypair = decode_keypair(&address, &keypair_str)?;
+    
+            println!("Loaded account: {}", address);
+            blockchain.keypairs.insert(address.clone(), Arc::new(keypair));
+        }
+
+        blockchain.ensure_account_entries();
+
+        if let Err(failure) = blockchain.validate_chain_with_progress(progress) {
+            println!("WARNING: Chain failed validation: {}", failure);
+        }
+
+        // Genesis funds belong to whoever holds the key the address was derived from
+        for address in blockchain.genesis_key_mismatches() {
+            println!("WARNING: Genesis address {} is registered with a mismatching key", address);
+        }
+        for address in blockchain.recorded_balance_mismatches() {
+            println!("WARNING: Recorded balance of {} does not match the chain", address);
+        }
+    
+        Ok(blockchain)
+    }
+    
+    
+    
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn chain_summary_handles_multibyte_validators() {
+        let mut blockchain = Blockchain::new("genesis");
+        let previous_hash = blockchain.get_latest_block().hash.clone();
+        blockchain.chain.push(Block::new(1, Vec::new(), previous_hash, "validätör-ñø-ümlaut".to_string()));
+
+        let summary = blockchain.chain_summary();
+        assert_eq!(summary.len(), 2);
+        assert!(summary[0].starts_with("#0 "));
+        assert!(summary[1].contains("validator=validätör-ñø "));
+    }
+
+    #[test]
+    fn resending_a_seen_transaction_is_dropped_quietly() {
+        let keypair = Keypair::generate(&mut rand::rngs::OsRng);
+        let sender = keypair.verifier().address();
+        let mut blockchain = Blockchain::new(&sender);
+        blockchain.register_keypair(Keypair::from_bytes(&keypair.to_bytes()).unwrap());
+
+        let transaction = Transaction::new(sender.clone(), "recipient".to_string(), 10.0);
+        blockchain.add_transaction(transaction.clone(), &keypair).unwrap();
+        assert_eq!(blockchain.add_transaction(transaction, &keypair), Ok(()));
+        assert_eq!(blockchain.pending_transactions.len(), 1);
+    }
+
+    fn block_at(blockchain: &Blockchain, timestamp: u64) -> Block {
+        let latest = blockchain.get_latest_block();
+        let mut block = Block::new(latest.index + 1, Vec::new(), latest.hash.clone(), "validator".to_string());
+        block.timestamp = timestamp;
+        block
+    }
+
+    #[test]
+    fn timeseries_starts_after_genesis() {
+        let mut blockchain = Blockchain::new("genesis");
+        blockchain.chain[0].timestamp = 0;
+        for timestamp in [1_700_000_000, 1_700_000_030, 1_700_000_125] {
+            let block = block_at(&blockchain, timestamp);
+            blockchain.chain.push(block);
+        }
+
+        let buckets = blockchain.timeseries(60);
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0].start, 1_699_999_980);
+        assert_eq!(blockchain.timeseries(3600).len(), 1);
+    }
+
+    #[test]
+    fn timeseries_caps_the_number_of_buckets() {
+        let mut blockchain = Blockchain::new("genesis");
+        for timestamp in [1, 1_700_000_000] {
+            let block = block_at(&blockchain, timestamp);
+            blockchain.chain.push(block);
+        }
+
+        let buckets = blockchain.timeseries(1);
+        assert_eq!(buckets.len() as u64, MAX_TIMESERIES_BUCKETS);
+        assert_eq!(buckets.last().unwrap().start, 1_700_000_000);
+        assert!(blockchain.timeseries(0).is_empty());
+    }
+
+    #[test]
+    fn set_config_rejects_invalid_values() {
+        let mut blockchain = Blockchain::new("genesis");
+        let invalid = ChainConfig { block_reward: -5.0, ..ChainConfig::default() };
+        assert!(blockchain.set_config(invalid).is_err());
+        assert_eq!(blockchain.config(), &ChainConfig::default());
+
+        let valid = ChainConfig { block_reward: 5.0, ..ChainConfig::default() };
+        blockchain.set_config(valid.clone()).unwrap();
+        assert_eq!(blockchain.config(), &valid);
+    }
+
+    /// A dev-mode chain whose genesis funds a registered validator, with the given block reward
+    fn rewarding_chain(block_reward: f64) -> (Blockchain, String) {
+        let keypair = Keypair::generate(&mut rand::rngs::OsRng);
+        let address = keypair.verifier().address();
+        let mut blockchain = Blockchain::new(&address);
+        blockchain.dev_mode = true;
+        blockchain.register_keypair(keypair);
+        blockchain.add_validator(address.clone()).unwrap();
+        blockchain.set_config(ChainConfig { block_reward, ..ChainConfig::default() }).unwrap();
+        (blockchain, address)
+    }
+
+    /// The next block on `blockchain`, signed by the validator when its key is held
+    fn next_block(blockchain: &Blockchain, validator: &str, transactions: Vec<Transaction>) -> Block {
+        let latest = blockchain.get_latest_block();
+        let mut block = Block::new(latest.index + 1, transactions, latest.hash.clone(), validator.to_string());
+        if let Some(keypair) = blockchain.keypairs.get(validator) {
+            block.sign(keypair.as_ref()).unwrap();
+        }
+        block
+    }
+
+    fn minted(recipient: &str, amount: f64) -> Transaction {
+        Transaction::new("0".to_string(), recipient.to_string(), amount)
+    }
+
+    fn failure_kind(result: Result<(), BlockchainError>) -> Option<FailureKind> {
+        match result {
+            Err(BlockchainError::InvalidBlock(failure)) => Some(failure.kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn append_block_accepts_a_single_reward_to_the_validator() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        let block = next_block(&blockchain, &validator, vec![minted(&validator, 5.0)]);
+        blockchain.append_block(block).unwrap();
+        assert_eq!(blockchain.get_account_balance(&validator), 1005.0);
+    }
+
+    #[test]
+    fn append_block_rejects_extra_minting() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        let invalid = [
+            vec![minted(&validator, 5.0), minted(&validator, 5.0)],
+            vec![minted(&validator, 500.0)],
+            vec![minted("someone-else", 5.0)],
+            vec![minted(&validator, 5.0).with_fee(100.0)],
+        ];
+        for transactions in invalid {
+            let block = next_block(&blockchain, &validator, transactions);
+            assert_eq!(failure_kind(blockchain.append_block(block)), Some(FailureKind::Coinbase));
+        }
+
+        let (mut unrewarded, validator) = rewarding_chain(0.0);
+        let block = next_block(&unrewarded, &validator, vec![minted(&validator, 1.0)]);
+        assert_eq!(failure_kind(unrewarded.append_block(block)), Some(FailureKind::Coinbase));
+    }
+
+    #[test]
+    fn replacement_chain_that_inflates_supply_is_rejected() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        let inflated = next_block(&blockchain, &validator, vec![minted(&validator, 5.0), minted(&validator, 1e6)]);
+        let candidate = vec![blockchain.chain[0].clone(), inflated];
+
+        let err = blockchain.try_replace_chain(candidate).unwrap_err();
+        assert!(err.contains("Block #1"), "{}", err);
+        assert_eq!(blockchain.chain.len(), 1);
+    }
+
+    #[test]
+    fn append_block_enforces_the_producer_turn() {
+        let (mut blockchain, first) = rewarding_chain(0.0);
+        let keypair = Keypair::generate(&mut rand::rngs::OsRng);
+        let second = blockchain.register_keypair(keypair);
+        blockchain.add_validator(second.clone()).unwrap();
+        let config = ChainConfig { consensus_mode: ConsensusMode::RoundRobin, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+
+        let producer = blockchain.next_block_producer().unwrap();
+        let other = if producer == first { second } else { first };
+        let out_of_turn = next_block(&blockchain, &other, Vec::new());
+        assert_eq!(failure_kind(blockchain.append_block(out_of_turn)), Some(FailureKind::Producer));
+
+        let in_turn = next_block(&blockchain, &producer, Vec::new());
+        blockchain.append_block(in_turn).unwrap();
+    }
+
+    #[test]
+    fn block_reward_is_fixed_once_blocks_exist() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        blockchain.mine_empty_blocks(1, &validator).unwrap();
+        let config = ChainConfig { block_reward: 50.0, ..blockchain.config().clone() };
+        assert!(blockchain.set_config(config).is_err());
+    }
+
+    fn save_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("blockchain-save-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn path_in(dir: &Path, name: &str) -> String {
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn failed_accounts_write_leaves_the_chain_file_untouched() {
+        let dir = save_dir("write");
+        let (chain_file, accounts_file) = (path_in(&dir, "chain.json"), path_in(&dir, "accounts.json"));
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        blockchain.save_retry = SaveRetry { retries: 0, backoff: Duration::ZERO };
+        blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+        let saved = fs::read_to_string(&chain_file).unwrap();
+
+        // A directory where the temporary accounts file should go makes writing it fail
+        fs::create_dir(format!("{}.tmp", accounts_file)).unwrap();
+        let result = blockchain.commit(&chain_file, &accounts_file, |chain| {
+            chain.mine_empty_blocks(1, &validator).map_err(|e| e.to_string())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(blockchain.chain.len(), 1);
+        assert_eq!(fs::read_to_string(&chain_file).unwrap(), saved);
+        assert!(!Path::new(&format!("{}.tmp", chain_file)).exists());
+    }
+
+    #[test]
+    fn failed_accounts_rename_restores_the_chain_file() {
+        let dir = save_dir("rename");
+        let chain_file = path_in(&dir, "chain.json");
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        blockchain.save_retry = SaveRetry { retries: 0, backoff: Duration::ZERO };
+        blockchain.save_to_file(&chain_file, &path_in(&dir, "accounts.json")).unwrap();
+        let saved = fs::read_to_string(&chain_file).unwrap();
+
+        // A file cannot be renamed over a non-empty directory
+        let blocked_accounts = path_in(&dir, "blocked");
+        fs::create_dir_all(dir.join("blocked").join("occupied")).unwrap();
+        let result = blockchain.commit(&chain_file, &blocked_accounts, |chain| {
+            chain.mine_empty_blocks(1, &validator).map_err(|e| e.to_string())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(blockchain.chain.len(), 1);
+        assert_eq!(fs::read_to_string(&chain_file).unwrap(), saved);
+        assert!(!Path::new(&format!("{}.tmp", blocked_accounts)).exists());
+    }
+
+    #[test]
+    fn receipt_references_the_confirming_block() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        let transaction = Transaction::new(validator.clone(), recipient, 10.0).with_fee(1.5);
+        let tx_hash = transaction.calculate_hash();
+        blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        let block = blockchain.create_block(&validator).unwrap();
+        blockchain.mine_empty_blocks(1, &validator).unwrap();
+
+        let receipt = blockchain.receipt(&tx_hash).unwrap();
+        assert_eq!(receipt.block_index, block.index);
+        assert_eq!(receipt.block_hash, block.hash);
+        // The block reward comes first
+        assert_eq!(receipt.position, 1);
+        assert_eq!(receipt.fee, 1.5);
+        assert_eq!(receipt.confirmations, 2);
+        assert!(blockchain.receipt("unknown").is_none());
+    }
+
+    #[test]
+    fn fingerprint_changes_with_any_block() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        blockchain.mine_empty_blocks(2, &validator).unwrap();
+        let copy: Blockchain = serde_json::from_str(&serde_json::to_string(&blockchain).unwrap()).unwrap();
+        assert_eq!(copy.fingerprint(), blockchain.fingerprint());
+
+        for index in 0..blockchain.chain.len() {
+            let mut mutated = copy.chain.clone();
+            mutated[index].timestamp += 1;
+            mutated[index].hash = mutated[index].calculate_hash();
+            let mutated = Blockchain { chain: mutated, ..Blockchain::empty(ChainConfig::default()) };
+            assert_ne!(mutated.fingerprint(), blockchain.fingerprint(), "block #{}", index);
+        }
+    }
+
+    #[test]
+    fn transfers_below_the_dust_threshold_are_rejected() {
+        let (mut blockchain, sender) = rewarding_chain(0.0);
+        let config = ChainConfig { min_transfer: 1.0, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&sender].clone();
+
+        let dust = Transaction::new(sender.clone(), recipient.clone(), 0.5);
+        assert!(matches!(
+            blockchain.add_transaction(dust, signer.as_ref()),
+            Err(BlockchainError::DustAmount { .. })
+        ));
+        let at_threshold = Transaction::new(sender, recipient, 1.0);
+        blockchain.add_transaction(at_threshold, signer.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn genesis_distribution_sums_allocations_per_recipient() {
+        let config = GenesisConfig {
+            allocations: vec![("alice".to_string(), 10.0), ("bob".to_string(), 5.0), ("alice".to_string(), 2.5)],
+            ..GenesisConfig::default()
+        };
+        let blockchain = Blockchain::from_genesis(&config).unwrap();
+        assert_eq!(
+            blockchain.genesis_distribution(),
+            vec![("alice".to_string(), 12.5), ("bob".to_string(), 5.0)]
+        );
+        assert!(Blockchain::empty(ChainConfig::default()).genesis_distribution().is_empty());
+    }
+
+    #[test]
+    fn watchers_fire_once_with_the_confirming_block() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        let transaction = Transaction::new(validator.clone(), recipient, 10.0);
+        let tx_hash = transaction.calculate_hash();
+
+        let confirmed = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let seen = confirmed.clone();
+        blockchain.watch_transaction(&tx_hash, Box::new(move |tx, block| {
+            seen.borrow_mut().push((tx.calculate_hash(), block.index));
+        }));
+        blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+        blockchain.mine_empty_blocks(1, &validator).unwrap();
+
+        assert_eq!(*confirmed.borrow(), vec![(tx_hash, 1)]);
+    }
+
+    #[test]
+    fn transactions_left_out_of_a_block_stay_pending() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let config = ChainConfig { max_block_transactions: 1, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        for fee in [1.0, 2.0] {
+            let transaction = Transaction::new(validator.clone(), recipient.clone(), 10.0).with_fee(fee);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+
+        let block = blockchain.create_block(&validator).unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(block.transactions[0].fee, 2.0);
+        assert_eq!(blockchain.pending_transactions.len(), 1);
+        assert_eq!(blockchain.pending_transactions[0].fee, 1.0);
+    }
+
+    /// Copies the persisted state of a chain, leaving keys and watchers behind
+    fn copy_of(blockchain: &Blockchain) -> Blockchain {
+        let mut copy: Blockchain = serde_json::from_str(&serde_json::to_string(blockchain).unwrap()).unwrap();
+        copy.rebuild_address_index();
+        copy
+    }
+
+    #[test]
+    fn reorgs_deeper_than_the_limit_are_rejected() {
+        let (mut blockchain, first) = rewarding_chain(0.0);
+        let second = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.add_validator(second.clone()).unwrap();
+        let mut fork = copy_of(&blockchain);
+        fork.dev_mode = true;
+        fork.keypairs.insert(second.clone(), blockchain.keypairs[&second].clone());
+        blockchain.mine_empty_blocks(2, &first).unwrap();
+        fork.mine_empty_blocks(3, &second).unwrap();
+
+        let config = ChainConfig { max_reorg_depth: Some(1), ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let err = blockchain.try_replace_chain(fork.chain.clone()).unwrap_err();
+        assert!(err.contains("Reorg depth 2"), "{}", err);
+        assert_eq!(blockchain.chain[1].validator, first);
+
+        let config = ChainConfig { max_reorg_depth: Some(2), ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        assert!(blockchain.try_replace_chain(fork.chain.clone()).unwrap());
+        assert_eq!(blockchain.fingerprint(), fork.fingerprint());
+    }
+
+    #[test]
+    fn validator_earnings_count_each_validators_rewards_and_fees() {
+        let (mut blockchain, first) = rewarding_chain(5.0);
+        let second = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.add_validator(second.clone()).unwrap();
+        let user = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+
+        let signer = blockchain.keypairs[&first].clone();
+        let funding = Transaction::new(first.clone(), user.clone(), 100.0).with_fee(1.0);
+        blockchain.add_transaction(funding, signer.as_ref()).unwrap();
+        blockchain.create_block(&first).unwrap();
+
+        let signer = blockchain.keypairs[&user].clone();
+        let payment = Transaction::new(user, first.clone(), 10.0).with_fee(2.0);
+        blockchain.add_transaction(payment, signer.as_ref()).unwrap();
+        blockchain.create_block(&second).unwrap();
+
+        assert_eq!(blockchain.validator_earnings(&first), (5.0, 1.0));
+        assert_eq!(blockchain.validator_earnings(&second), (5.0, 2.0));
+        assert_eq!(blockchain.get_account_balance(&second), 7.0);
+        assert_eq!(blockchain.validator_earnings("nobody"), (0.0, 0.0));
+    }
+
+    #[test]
+    fn loading_reports_validated_blocks_in_order() {
+        let dir = save_dir("progress");
+        let (chain_file, accounts_file) = (path_in(&dir, "chain.json"), path_in(&dir, "accounts.json"));
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        blockchain.mine_empty_blocks(3, &validator).unwrap();
+        blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+
+        let mut reports = Vec::new();
+        Blockchain::load_from_file_with_progress(&chain_file, &accounts_file, None, &mut |done, total| {
+            reports.push((done, total))
+        })
+        .unwrap();
+        assert_eq!(reports, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+    }
+
+    #[test]
+    fn rewritten_accounts_file_holds_every_keypair() {
+        let dir = save_dir("accounts");
+        let accounts_file = path_in(&dir, "accounts.json");
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let other = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.save_accounts_to_file(&accounts_file).unwrap();
+
+        let written: HashMap<String, String> = serde_json::from_str(&fs::read_to_string(&accounts_file).unwrap()).unwrap();
+        assert_eq!(written.len(), 2);
+        for address in [validator, other] {
+            let keypair = decode_keypair(&address, &written[&address]).unwrap();
+            assert!(keypair_signs_for(&keypair, &address));
+        }
+    }
+
+    #[test]
+    fn fees_above_the_maximum_are_rejected() {
+        let (mut blockchain, sender) = rewarding_chain(0.0);
+        let config = ChainConfig { max_fee: Some(2.0), ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&sender].clone();
+
+        let oversized = Transaction::new(sender.clone(), recipient.clone(), 10.0).with_fee(2.5);
+        assert_eq!(
+            blockchain.add_transaction(oversized, signer.as_ref()),
+            Err(BlockchainError::FeeTooHigh { fee: 2.5, maximum: 2.0 })
+        );
+        let at_maximum = Transaction::new(sender, recipient, 10.0).with_fee(2.0);
+        blockchain.add_transaction(at_maximum, signer.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn last_activity_is_the_latest_block_touching_the_account() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let idle = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain.add_transaction(Transaction::new(validator.clone(), recipient.clone(), 10.0), signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+        blockchain.mine_empty_blocks(2, &validator).unwrap();
+
+        assert_eq!(blockchain.last_activity(&recipient), Some(1));
+        assert_eq!(blockchain.last_activity(&validator), Some(1));
+        assert_eq!(blockchain.last_activity(&idle), None);
+    }
+
+    #[test]
+    fn transactions_changed_after_signing_are_dropped_from_blocks() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        for amount in [10.0, 20.0] {
+            let transaction = Transaction::new(validator.clone(), recipient.clone(), amount);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+        blockchain.pending_transactions[0].amount = 500.0;
+
+        let block = blockchain.create_block(&validator).unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(block.transactions[0].amount, 20.0);
+        assert!(blockchain.pending_transactions.is_empty());
+        assert_eq!(blockchain.get_account_balance(&recipient), 20.0);
+    }
+
+    #[test]
+    fn account_stats_count_confirmed_activity() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.mine_empty_blocks(1, &validator).unwrap();
+        let signer = blockchain.keypairs[&validator].clone();
+        for amount in [10.0, 15.0] {
+            let transaction = Transaction::new(validator.clone(), recipient.clone(), amount).with_memo(&amount.to_string());
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+        blockchain.create_block(&validator).unwrap();
+
+        let stats = blockchain.account_stats(&recipient);
+        assert_eq!(stats.received_count, 2);
+        assert_eq!(stats.volume_received, 25.0);
+        assert_eq!(stats.sent_count, 0);
+        assert_eq!(stats.first_seen, Some(2));
+
+        let stats = blockchain.account_stats(&validator);
+        assert_eq!((stats.sent_count, stats.volume_sent), (2, 25.0));
+        assert_eq!(stats.first_seen, Some(0));
+        assert_eq!(blockchain.account_stats("nobody"), AccountStats::default());
+    }
+
+    #[test]
+    fn strict_validation_fails_on_warnings() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        assert!(blockchain.validate_chain_strict());
+
+        let latest = blockchain.get_latest_block();
+        let earlier = latest.timestamp - 10;
+        let mut block = Block::with_timestamp(1, Vec::new(), latest.hash.clone(), validator.clone(), earlier);
+        block.sign(blockchain.keypairs[&validator].as_ref()).unwrap();
+        blockchain.append_block(block).unwrap();
+
+        assert!(blockchain.validate_chain());
+        assert!(!blockchain.validate_chain_strict());
+        assert_eq!(blockchain.validation_warnings().len(), 1);
+    }
+
+    #[test]
+    fn validator_role_moves_in_one_step() {
+        let (mut blockchain, from) = rewarding_chain(0.0);
+        blockchain.mine_empty_blocks(1, &from).unwrap();
+        let to = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+
+        assert!(blockchain.transfer_validator_role(&from, &from).is_err());
+        assert!(blockchain.transfer_validator_role(&from, "unregistered").is_err());
+        assert!(blockchain.transfer_validator_role(&to, &from).is_err());
+
+        blockchain.transfer_validator_role(&from, &to).unwrap();
+        assert!(blockchain.is_validator(&to));
+        assert!(!blockchain.is_validator(&from));
+        // Blocks the old validator produced stay valid
+        assert!(blockchain.validate_chain());
+    }
+
+    #[test]
+    fn nodes_build_the_same_block_from_differently_ordered_mempools() {
+        let (mut first, validator) = rewarding_chain(0.0);
+        let recipient = first.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let mut second = copy_of(&first);
+        let signer = first.keypairs[&validator].clone();
+
+        let mut transactions = Vec::new();
+        for (timestamp, fee) in [(1_700_000_100, 1.0), (1_700_000_050, 1.0), (1_700_000_000, 2.0)] {
+            let mut transaction = Transaction::new(validator.clone(), recipient.clone(), 10.0).with_fee(fee);
+            transaction.timestamp = timestamp;
+            transaction.sign(signer.as_ref()).unwrap();
+            transactions.push(transaction);
+        }
+        for transaction in &transactions {
+            first.submit_signed_transaction(transaction.clone()).unwrap();
+        }
+        for transaction in transactions.iter().rev() {
+            second.submit_signed_transaction(transaction.clone()).unwrap();
+        }
+
+        let build = |blockchain: &Blockchain| {
+            let mut block = blockchain.preview_block(&validator).unwrap();
+            block.timestamp = 1_700_000_200;
+            block.hash = block.calculate_hash();
+            block
+        };
+        let block = build(&first);
+        assert_eq!(block.hash, build(&second).hash);
+        let order: Vec<(f64, u64)> = block.transactions.iter().map(|tx| (tx.fee, tx.timestamp)).collect();
+        assert_eq!(order, vec![(2.0, 1_700_000_000), (1.0, 1_700_000_050), (1.0, 1_700_000_100)]);
+
+        let mut reordered = block.clone();
+        reordered.transactions.swap(1, 2);
+        reordered.hash = reordered.calculate_hash();
+        assert_eq!(failure_kind(first.append_block(reordered)), Some(FailureKind::Ordering));
+    }
+
+
+    #[test]
+    fn unfunded_validators_take_their_round_robin_turn() {
+        let (mut blockchain, funded) = rewarding_chain(0.0);
+        assert!(blockchain.add_validator("unregistered".to_string()).is_err());
+
+        let unfunded = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.add_validator(unfunded.clone()).unwrap();
+        assert_eq!(blockchain.get_account_balance(&unfunded), 0.0);
+        assert_eq!(blockchain.validator_weight(&unfunded), 1.0);
+        assert_eq!(blockchain.total_validator_weight(), 1001.0);
+
+        let config = ChainConfig { consensus_mode: ConsensusMode::RoundRobin, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        if blockchain.next_block_producer().as_deref() == Some(funded.as_str()) {
+            let block = next_block(&blockchain, &funded, Vec::new());
+            blockchain.append_block(block).unwrap();
+        }
+        assert_eq!(blockchain.next_block_producer(), Some(unfunded.clone()));
+        let block = next_block(&blockchain, &unfunded, Vec::new());
+        blockchain.append_block(block).unwrap();
+        assert_eq!(blockchain.get_latest_block().validator, unfunded);
+        assert!(blockchain.validate_chain());
+    }
+
+
+    #[test]
+    fn storage_report_matches_the_saved_file() {
+        let dir = save_dir("storage");
+        let chain_file = path_in(&dir, "chain.json");
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        blockchain.mine_empty_blocks(3, &validator).unwrap();
+        blockchain.save_to_file(&chain_file, &path_in(&dir, "accounts.json")).unwrap();
+
+        let report = blockchain.storage_report(40);
+        let saved = fs::metadata(&chain_file).unwrap().len() as f64;
+        assert!((report.current_size as f64 - saved).abs() <= saved * 0.01, "{:?} vs {}", report, saved);
+        assert_eq!(report.average_block_size, report.current_size as f64 / 4.0);
+        assert_eq!(report.projected_size, report.current_size as f64 * 10.0);
+        assert_eq!(blockchain.storage_report(2).projected_size, report.current_size as f64);
+    }
+
+
+    #[test]
+    fn chains_saved_under_custom_names_validate_from_memory() {
+        let dir = save_dir("custom-names");
+        let (chain_file, accounts_file) = (path_in(&dir, "ledger.dat"), path_in(&dir, "keys.dat"));
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain
+            .add_transaction(Transaction::new(validator.clone(), "recipient".to_string(), 10.0), signer.as_ref())
+            .unwrap();
+        blockchain.create_block(&validator).unwrap();
+        blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+
+        let loaded = Blockchain::load_from_file(&chain_file, &accounts_file, None).unwrap();
+        assert!(loaded.validate_chain());
+        assert_eq!(loaded.get_account_balance("recipient"), 10.0);
+    }
+
+
+    #[test]
+    fn validator_transactions_jump_a_full_backlog_when_prioritized() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let user = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let validator_signer = blockchain.keypairs[&validator].clone();
+        let user_signer = blockchain.keypairs[&user].clone();
+        blockchain
+            .add_transaction(Transaction::new(validator.clone(), user.clone(), 100.0), validator_signer.as_ref())
+            .unwrap();
+        blockchain.create_block(&validator).unwrap();
+
+        for amount in 1..=4 {
+            let transaction = Transaction::new(user.clone(), "recipient".to_string(), amount as f64).with_fee(1.0);
+            blockchain.add_transaction(transaction, user_signer.as_ref()).unwrap();
+        }
+        let operational = Transaction::new(validator.clone(), "operations".to_string(), 5.0);
+        blockchain.add_transaction(operational, validator_signer.as_ref()).unwrap();
+
+        let config = ChainConfig { max_block_transactions: 2, ..blockchain.config().clone() };
+        blockchain.set_config(config.clone()).unwrap();
+        let senders = |blockchain: &Blockchain| -> Vec<String> {
+            blockchain.select_for_block().into_iter().map(|tx| tx.sender).collect()
+        };
+        assert_eq!(senders(&blockchain), vec![user.clone(), user.clone()]);
+
+        blockchain.set_config(ChainConfig { prioritize_validator_transactions: true, ..config }).unwrap();
+        let selected = senders(&blockchain);
+        assert_eq!(selected.len(), 2);
+        assert!(selected.contains(&validator));
+    }
+
+
+    #[test]
+    fn only_keys_without_balance_or_activity_are_orphaned() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let active = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let stale = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain
+            .add_transaction(Transaction::new(validator.clone(), active.clone(), 10.0), signer.as_ref())
+            .unwrap();
+        blockchain.create_block(&validator).unwrap();
+
+        // As if both were loaded from an accounts file that predates their balance entries
+        blockchain.accounts.remove(&active);
+        blockchain.accounts.remove(&stale);
+        assert_eq!(blockchain.orphaned_keypairs(), vec![stale]);
+    }
+
+
+    #[test]
+    fn fixed_genesis_timestamps_reproduce_the_genesis_hash() {
+        let config = GenesisConfig {
+            timestamp: Some(1_600_000_000),
+            ..GenesisConfig::single("founder", 1000.0)
+        };
+        let first = Blockchain::from_genesis(&config).unwrap();
+        let second = Blockchain::from_genesis(&config).unwrap();
+        assert_eq!(first.chain[0].timestamp, 1_600_000_000);
+        assert_eq!(first.chain[0].hash, second.chain[0].hash);
+
+        let later = Blockchain::from_genesis(&GenesisConfig { timestamp: Some(1_600_000_001), ..config }).unwrap();
+        assert_ne!(later.chain[0].hash, first.chain[0].hash);
+    }
+
+
+    #[test]
+    fn mempool_summary_totals_a_mixed_pool() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        for (amount, fee) in [(10.0, 0.0), (25.0, 0.5), (40.0, 2.0)] {
+            let transaction = Transaction::new(validator.clone(), "recipient".to_string(), amount).with_fee(fee);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+        let config = ChainConfig { max_block_transactions: 2, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+
+        let summary = blockchain.mempool_summary();
+        assert_eq!(summary, MempoolSummary {
+            transaction_count: 3,
+            total_value: 75.0,
+            total_fees: 2.5,
+            fits_in_next_block: 2,
+        });
+    }
+
+
+    /// Every (block index, position) touching each address, found by scanning the whole chain
+    fn scanned_index(blockchain: &Blockchain) -> HashMap<String, Vec<(u32, usize)>> {
+        let mut index: HashMap<String, Vec<(u32, usize)>> = HashMap::new();
+        for block in &blockchain.chain {
+            for (position, tx) in block.transactions.iter().enumerate() {
+                for address in [&tx.sender, &tx.recipient] {
+                    let entries = index.entry(address.clone()).or_default();
+                    if address != "0" && entries.last() != Some(&(block.index, position)) {
+                        entries.push((block.index, position));
+                    }
+                }
+            }
+        }
+        index.retain(|_, entries| !entries.is_empty());
+        index
+    }
+
+    #[test]
+    fn address_index_matches_a_full_scan_as_blocks_are_mined() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        let user = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        assert_eq!(blockchain.address_index, scanned_index(&blockchain));
+
+        let signer = blockchain.keypairs[&validator].clone();
+        for amount in [10.0, 20.0] {
+            let transaction = Transaction::new(validator.clone(), user.clone(), amount);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+            blockchain.create_block(&validator).unwrap();
+            assert_eq!(blockchain.address_index, scanned_index(&blockchain));
+        }
+        assert_eq!(blockchain.address_index[&user], vec![(1, 1), (2, 1)]);
+        assert_eq!(blockchain.participants(), HashSet::from([validator, user]));
+
+        assert_eq!(copy_of(&blockchain).address_index, blockchain.address_index);
+    }
+
+
+    #[test]
+    fn participants_are_the_transacting_addresses_only() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let idle = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        for recipient in ["alice", "bob"] {
+            let transaction = Transaction::new(validator.clone(), recipient.to_string(), 10.0);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+        blockchain.create_block(&validator).unwrap();
+
+        let appearing: HashSet<String> = blockchain
+            .chain
+            .iter()
+            .flat_map(|block| &block.transactions)
+            .flat_map(|tx| [tx.sender.clone(), tx.recipient.clone()])
+            .filter(|address| address != "0")
+            .collect();
+        let participants = blockchain.participants();
+        assert_eq!(participants, appearing);
+        assert_eq!(participants.len(), 3);
+        assert!(!participants.contains(&idle) && blockchain.accounts.contains_key(&idle));
+    }
+
+
+    #[test]
+    fn loading_with_a_passphrase_rejects_a_modified_accounts_file() {
+        let dir = save_dir("signed-accounts");
+        let (chain_file, accounts_file) = (path_in(&dir, "chain.json"), path_in(&dir, "accounts.json"));
+        let (mut blockchain, _) = rewarding_chain(0.0);
+        blockchain.accounts_passphrase = Some("secret".to_string());
+        blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+
+        let loaded = Blockchain::load_from_file(&chain_file, &accounts_file, Some("secret")).unwrap();
+        assert_eq!(loaded.accounts_passphrase.as_deref(), Some("secret"));
+        assert!(Blockchain::load_from_file(&chain_file, &accounts_file, Some("wrong")).is_err());
+
+        let substituted = Keypair::generate(&mut rand::rngs::OsRng);
+        let mut accounts: HashMap<String, String> =
+            serde_json::from_str(&fs::read_to_string(&accounts_file).unwrap()).unwrap();
+        let encoded = format!("{}:{}", hex::encode(substituted.secret.as_bytes()), hex::encode(substituted.public.as_bytes()));
+        accounts.insert(substituted.verifier().address(), encoded);
+        fs::write(&accounts_file, serde_json::to_string_pretty(&accounts).unwrap()).unwrap();
+        match Blockchain::load_from_file(&chain_file, &accounts_file, Some("secret")) {
+            Err(e) => assert!(e.contains("was modified"), "{}", e),
+            Ok(_) => panic!("a modified accounts file was loaded"),
+        }
+    }
+
+
+    #[test]
+    fn inclusion_is_verified_against_the_chosen_block() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        for amount in [10.0, 20.0, 30.0] {
+            let transaction = Transaction::new(validator.clone(), "recipient".to_string(), amount);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+        blockchain.create_block(&validator).unwrap();
+
+        let included = blockchain.chain[1].transactions[2].calculate_hash();
+        assert_eq!(blockchain.verify_transaction_inclusion(1, &included), Ok(true));
+        assert_eq!(blockchain.verify_transaction_inclusion(0, &included), Ok(false));
+        assert_eq!(blockchain.verify_transaction_inclusion(1, "not-a-transaction"), Ok(false));
+        assert!(blockchain.verify_transaction_inclusion(2, &included).is_err());
+    }
+
+
+    #[test]
+    fn a_full_mempool_evicts_its_lowest_fee_transaction_for_a_better_one() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        let config = ChainConfig {
+            max_mempool_size: 3,
+            mempool_policy: MempoolPolicy::EvictLowestFee,
+            ..blockchain.config().clone()
+        };
+        blockchain.set_config(config).unwrap();
+        let transfer = |amount: f64, fee: f64| {
+            Transaction::new(validator.clone(), "recipient".to_string(), amount).with_fee(fee)
+        };
+        for (amount, fee) in [(1.0, 0.5), (2.0, 0.1), (3.0, 0.3)] {
+            blockchain.add_transaction(transfer(amount, fee), signer.as_ref()).unwrap();
+        }
+
+        assert_eq!(
+            blockchain.add_transaction(transfer(4.0, 0.05), signer.as_ref()),
+            Err(BlockchainError::MempoolFull { capacity: 3 })
+        );
+        blockchain.add_transaction(transfer(5.0, 0.2), signer.as_ref()).unwrap();
+        let mut fees: Vec<f64> = blockchain.pending_transactions.iter().map(|tx| tx.fee).collect();
+        fees.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(fees, vec![0.2, 0.3, 0.5]);
+    }
+
+
+    #[test]
+    fn state_root_depends_only_on_balances() {
+        let first = Blockchain::from_genesis(&GenesisConfig::single("founder", 1000.0)).unwrap();
+        let mut second = Blockchain::from_genesis(&GenesisConfig {
+            timestamp: Some(1_600_000_000),
+            ..GenesisConfig::single("founder", 1000.0)
+        })
+        .unwrap();
+        assert_ne!(first.chain[0].hash, second.chain[0].hash);
+        assert_eq!(first.state_root(), second.state_root());
+
+        *second.accounts.get_mut("founder").unwrap() -= 0.01;
+        assert_ne!(first.state_root(), second.state_root());
+    }
+
+
+    #[test]
+    fn simulated_balances_match_the_mined_outcome() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let sender = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let recipient = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let validator_signer = blockchain.keypairs[&validator].clone();
+        blockchain
+            .add_transaction(Transaction::new(validator.clone(), sender.clone(), 100.0), validator_signer.as_ref())
+            .unwrap();
+        blockchain.create_block(&validator).unwrap();
+
+        let transaction = Transaction::new(sender.clone(), recipient.clone(), 30.0).with_fee(0.5);
+        let simulated = blockchain.simulate(&transaction).unwrap();
+        assert_eq!(blockchain.get_account_balance(&sender), 100.0);
+
+        let signer = blockchain.keypairs[&sender].clone();
+        blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+        assert_eq!(simulated, (blockchain.get_account_balance(&sender), blockchain.get_account_balance(&recipient)));
+        assert_eq!(simulated, (69.5, 30.0));
+
+        let overdraft = Transaction::new(sender.clone(), recipient, 1000.0);
+        assert_eq!(blockchain.simulate(&overdraft), Err(BlockchainError::InsufficientBalance));
+    }
+
+
+    #[test]
+    fn validators_idle_beyond_the_window_are_flagged() {
+        let (mut blockchain, active) = rewarding_chain(0.0);
+        let idle = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.add_validator(idle.clone()).unwrap();
+        for _ in 0..3 {
+            let block = next_block(&blockchain, &active, Vec::new());
+            blockchain.append_block(block).unwrap();
+        }
+
+        assert_eq!(blockchain.inactive_validators(2), vec![idle.clone()]);
+        assert!(blockchain.inactive_validators(3).is_empty());
+
+        let block = next_block(&blockchain, &idle, Vec::new());
+        blockchain.append_block(block).unwrap();
+        assert!(blockchain.inactive_validators(1).is_empty());
+        assert_eq!(blockchain.inactive_validators(0), vec![active]);
+    }
+
+
+    #[test]
+    fn transactions_between_keeps_only_the_inclusive_range() {
+        let mut blockchain = Blockchain::new("genesis");
+        blockchain.chain[0].transactions[0].timestamp = 0;
+        for (block_timestamp, tx_timestamps) in [(1_000, vec![900, 1_000]), (2_000, vec![1_500, 2_000, 2_001])] {
+            let mut block = block_at(&blockchain, block_timestamp);
+            for timestamp in tx_timestamps {
+                let mut transaction = Transaction::new("alice".to_string(), "bob".to_string(), timestamp as f64);
+                transaction.timestamp = timestamp;
+                block.transactions.push(transaction);
+            }
+            blockchain.chain.push(block);
+        }
+
+        let found: Vec<(u32, u64)> = blockchain
+            .transactions_between(1_000, 2_000)
+            .into_iter()
+            .map(|(index, tx)| (index, tx.timestamp))
+            .collect();
+        assert_eq!(found, vec![(1, 1_000), (2, 1_500), (2, 2_000)]);
+        assert!(blockchain.transactions_between(2_500, 3_000).is_empty());
+    }
+
+
+    #[test]
+    fn pending_deltas_debit_the_sender_and_credit_the_recipient() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        let transaction = Transaction::new(validator.clone(), "recipient".to_string(), 40.0).with_fee(0.5);
+        blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+
+        let mut expected = vec![(validator, -40.5), ("recipient".to_string(), 40.0)];
+        expected.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(blockchain.pending_deltas(), expected);
+    }
+
+
+    #[test]
+    fn diagnose_pinpoints_a_broken_link() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        for _ in 0..3 {
+            let block = next_block(&blockchain, &validator, Vec::new());
+            blockchain.append_block(block).unwrap();
+        }
+        assert!(blockchain.diagnose().is_empty());
+
+        let expected = blockchain.chain[1].hash.clone();
+        blockchain.chain[2].previous_hash = "f".repeat(64);
+        blockchain.chain[2].hash = blockchain.chain[2].calculate_hash();
+        blockchain.chain[3].previous_hash = blockchain.chain[2].hash.clone();
+        blockchain.chain[3].hash = blockchain.chain[3].calculate_hash();
+
+        assert_eq!(
+            blockchain.diagnose(),
+            vec![ChainProblem::BrokenLink { index: 2, expected, actual: "f".repeat(64) }]
+        );
+    }
+
+
+    #[test]
+    fn transfers_above_the_cap_are_rejected_despite_the_balance() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let config = ChainConfig { max_transaction_amount: Some(100.0), ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let signer = blockchain.keypairs[&validator].clone();
+
+        let large = Transaction::new(validator.clone(), "recipient".to_string(), 100.01);
+        assert_eq!(
+            blockchain.add_transaction(large, signer.as_ref()),
+            Err(BlockchainError::AmountTooLarge { amount: 100.01, maximum: 100.0 })
+        );
+        let at_cap = Transaction::new(validator.clone(), "recipient".to_string(), 100.0);
+        blockchain.add_transaction(at_cap, signer.as_ref()).unwrap();
+        assert_eq!(blockchain.pending_transactions.len(), 1);
+    }
+
+
+    #[test]
+    fn registering_a_genesis_address_keeps_its_allocation_and_key_link() {
+        let owner = Keypair::generate(&mut rand::rngs::OsRng);
+        let address = owner.verifier().address();
+        let mut blockchain = Blockchain::new(&address);
+        assert_eq!(blockchain.register_keypair(owner), address);
+        assert_eq!(blockchain.get_account_balance(&address), 1000.0);
+        assert!(blockchain.genesis_key_mismatches().is_empty());
+
+        // As if the accounts file paired the genesis address with someone else's key
+        let impostor = Keypair::generate(&mut rand::rngs::OsRng);
+        blockchain.keypairs.remove(&address);
+        blockchain.public_keys.insert(address.clone(), impostor.public);
+        assert_eq!(blockchain.genesis_key_mismatches(), vec![address]);
+    }
+
+
+    #[test]
+    fn small_block_size_flags_blocks_below_the_threshold() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let config = ChainConfig { min_block_transactions_warn: 3, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        assert_eq!(blockchain.small_block_size(), None);
+
+        let signer = blockchain.keypairs[&validator].clone();
+        for amount in [1.0, 2.0, 3.0] {
+            let transaction = Transaction::new(validator.clone(), "recipient".to_string(), amount);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+            let expected = if amount < 3.0 { Some(amount as usize) } else { None };
+            assert_eq!(blockchain.small_block_size(), expected);
+        }
+    }
+
+
+    #[test]
+    fn merging_a_mempool_admits_only_new_valid_transactions() {
+        let (mut local, validator) = rewarding_chain(0.0);
+        let mut peer = copy_of(&local);
+        let signer = local.keypairs[&validator].clone();
+        let transfer = |amount: f64| Transaction::new(validator.clone(), "recipient".to_string(), amount);
+        for amount in [10.0, 20.0, 980.0] {
+            peer.add_transaction(transfer(amount), signer.as_ref()).unwrap();
+        }
+        let shared = peer.pending_transactions[0].clone();
+        local.submit_signed_transaction(shared.clone()).unwrap();
+        let mut tampered = peer.pending_transactions[1].clone();
+        tampered.amount = 5.0;
+
+        let mut offered = peer.pending_transactions.clone();
+        offered.push(tampered);
+        assert_eq!(local.merge_mempool(&offered), 1);
+
+        let amounts: Vec<f64> = local.pending_transactions.iter().map(|tx| tx.amount).collect();
+        assert_eq!(amounts, vec![10.0, 20.0]);
+        assert_eq!(local.merge_mempool(&offered), 0);
+    }
+
+
+    #[test]
+    fn validator_weight_follows_the_balance() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let user = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        assert_eq!(blockchain.validator_weight(&validator), 1000.0);
+
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain
+            .add_transaction(Transaction::new(validator.clone(), user.clone(), 300.0), signer.as_ref())
+            .unwrap();
+        assert_eq!(blockchain.validator_weight(&validator), 1000.0);
+        blockchain.create_block(&validator).unwrap();
+
+        assert_eq!(blockchain.validator_weight(&validator), 700.0);
+        assert_eq!(blockchain.validator_weight(&user), 0.0);
+        blockchain.add_validator(user.clone()).unwrap();
+        assert_eq!(blockchain.validator_weight(&user), 300.0);
+    }
+
+
+    #[test]
+    fn the_genesis_validator_survives_save_and_load() {
+        let dir = save_dir("genesis-validator");
+        let (chain_file, accounts_file) = (path_in(&dir, "chain.json"), path_in(&dir, "accounts.json"));
+        let keypair = Keypair::generate(&mut rand::rngs::OsRng);
+        let address = keypair.verifier().address();
+
+        let mut unfunded_genesis = GenesisConfig::single(&address, 1000.0);
+        unfunded_genesis.validators.clear();
+        let built = [
+            (Blockchain::new(&address), true),
+            (Blockchain::from_genesis(&GenesisConfig::single(&address, 1000.0)).unwrap(), true),
+            (Blockchain::from_genesis(&unfunded_genesis).unwrap(), false),
+        ];
+        for (mut blockchain, validator) in built {
+            blockchain.register_keypair(Keypair::from_bytes(&keypair.to_bytes()).unwrap());
+            assert_eq!(blockchain.is_validator(&address), validator);
+            blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+
+            let loaded = Blockchain::load_from_file(&chain_file, &accounts_file, None).unwrap();
+            assert_eq!(loaded.validators, blockchain.validators);
+            assert_eq!(loaded.is_validator(&address), validator);
+        }
+    }
+
+
+    #[test]
+    fn reconciliation_sorts_each_kind_of_drift() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain
+            .add_transaction(Transaction::new(validator.clone(), "recipient".to_string(), 10.0), signer.as_ref())
+            .unwrap();
+        blockchain.create_block(&validator).unwrap();
+
+        let stale = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.accounts.remove(&stale);
+        let mislabeled = "0a".repeat(32);
+        blockchain.keypairs.insert(mislabeled.clone(), Arc::new(Keypair::generate(&mut rand::rngs::OsRng)));
+
+        let mut orphaned = vec![stale, mislabeled.clone()];
+        orphaned.sort();
+        assert_eq!(blockchain.reconcile_accounts(), AccountReconciliation {
+            missing_keypairs: vec!["recipient".to_string()],
+            orphaned_keypairs: orphaned,
+            derivation_mismatches: vec![mislabeled],
+        });
+    }
+
+
+    #[test]
+    fn amounts_beyond_exact_range_fail_cleanly() {
+        let keypair = Keypair::generate(&mut rand::rngs::OsRng);
+        let sender = keypair.verifier().address();
+        let config = GenesisConfig {
+            allocations: vec![(sender.clone(), 1000.0), ("whale".to_string(), MAX_AMOUNT - 10.0)],
+            validators: vec![sender.clone()],
+            ..GenesisConfig::default()
+        };
+        let mut blockchain = Blockchain::from_genesis(&config).unwrap();
+        blockchain.register_keypair(keypair);
+        let signer = blockchain.keypairs[&sender].clone();
+
+        for amount in [f64::NAN, f64::INFINITY, -1.0, MAX_AMOUNT * 2.0] {
+            let transaction = Transaction::new(sender.clone(), "recipient".to_string(), amount);
+            assert!(
+                matches!(blockchain.add_transaction(transaction, signer.as_ref()), Err(BlockchainError::AmountOutOfRange { .. })),
+                "{}",
+                amount
+            );
+        }
+        let overflowing = Transaction::new(sender.clone(), "whale".to_string(), 20.0);
+        assert_eq!(
+            blockchain.add_transaction(overflowing, signer.as_ref()),
+            Err(BlockchainError::BalanceOverflow { address: "whale".to_string() })
+        );
+
+        // Each transfer fits on its own; together they overflow when the block is applied
+        let mut transfers = Vec::new();
+        for fee in [0.0, 0.1] {
+            let mut transaction = Transaction::new(sender.clone(), "whale".to_string(), 8.0).with_fee(fee);
+            transaction.sign(signer.as_ref()).unwrap();
+            transfers.push(transaction);
+        }
+        transfers.sort_by(canonical_order);
+        let block = next_block(&blockchain, &sender, transfers);
+        assert!(blockchain.append_block(block).is_err());
+        assert_eq!(blockchain.get_account_balance("whale"), MAX_AMOUNT - 10.0);
+        assert_eq!(blockchain.chain.len(), 1);
+    }
+
+
+    #[test]
+    fn preview_block_matches_the_block_created_next() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        for (recipient, fee) in [("alice", 0.5), ("bob", 2.0), ("carol", 0.0)] {
+            let transaction = Transaction::new(validator.clone(), recipient.to_string(), 10.0).with_fee(fee);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+        let fingerprint = blockchain.fingerprint();
+
+        let preview = blockchain.preview_block(&validator).unwrap();
+        assert_eq!(blockchain.fingerprint(), fingerprint);
+        assert_eq!(blockchain.pending_transactions.len(), 3);
+        assert_eq!(blockchain.get_account_balance(&validator), 1000.0);
+
+        let created = blockchain.create_block(&validator).unwrap();
+        let hashes = |block: &Block| block.transactions.iter().map(|tx| tx.calculate_hash()).collect::<Vec<_>>();
+        assert_eq!(hashes(&preview), hashes(&created));
+        assert_eq!((preview.index, &preview.previous_hash), (created.index, &created.previous_hash));
+        assert_eq!(preview.validator, created.validator);
+    }
+
+
+    #[test]
+    fn block_at_the_wrong_height_fails_the_index_check() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let mut block = next_block(&blockchain, &validator, Vec::new());
+        block.index += 1;
+        block.hash = block.calculate_hash();
+        assert_eq!(failure_kind(blockchain.append_block(block)), Some(FailureKind::Index));
+    }
+
+    #[test]
+    fn block_edited_after_hashing_fails_the_hash_check() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let mut block = next_block(&blockchain, &validator, Vec::new());
+        block.timestamp += 1;
+        assert_eq!(failure_kind(blockchain.append_block(block)), Some(FailureKind::Hash));
+    }
+
+    #[test]
+    fn block_pointing_elsewhere_fails_the_link_check() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let mut block = next_block(&blockchain, &validator, Vec::new());
+        block.previous_hash = "0".repeat(64);
+        block.hash = block.calculate_hash();
+        assert_eq!(failure_kind(blockchain.append_block(block)), Some(FailureKind::Link));
+    }
+
+    #[test]
+    fn transaction_edited_after_signing_fails_the_signature_check() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        let mut transaction = Transaction::new(validator.clone(), "alice".to_string(), 10.0);
+        transaction.sign(signer.as_ref()).unwrap();
+        transaction.amount = 500.0;
+        let block = next_block(&blockchain, &validator, vec![transaction]);
+        assert_eq!(failure_kind(blockchain.append_block(block)), Some(FailureKind::Signature));
+    }
+
+    #[test]
+    fn block_from_a_non_validator_fails_the_validator_check() {
+        let (mut blockchain, _) = rewarding_chain(0.0);
+        let block = next_block(&blockchain, "mallory", Vec::new());
+        assert_eq!(failure_kind(blockchain.append_block(block)), Some(FailureKind::Validator));
+    }
+
+    #[test]
+    fn unsigned_or_forged_block_fails_the_producer_signature_check() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let latest = blockchain.get_latest_block();
+        let unsigned = Block::new(latest.index + 1, Vec::new(), latest.hash.clone(), validator.clone());
+        assert_eq!(failure_kind(blockchain.append_block(unsigned)), Some(FailureKind::ProducerSignature));
+
+        let mut forged = next_block(&blockchain, &validator, Vec::new());
+        forged.sign(&Keypair::generate(&mut rand::rngs::OsRng)).unwrap();
+        assert_eq!(failure_kind(blockchain.append_block(forged)), Some(FailureKind::ProducerSignature));
+
+        let mut altered = next_block(&blockchain, &validator, Vec::new());
+        altered.timestamp += 1;
+        altered.hash = altered.calculate_hash();
+        assert_eq!(failure_kind(blockchain.append_block(altered)), Some(FailureKind::ProducerSignature));
+
+        blockchain.append_block(next_block(&blockchain, &validator, Vec::new())).unwrap();
+        blockchain.chain[1].validator_signature = None;
+        assert!(blockchain.validate_chain());
+        assert_eq!(blockchain.validation_warnings(), vec!["Block #1 is not signed by its validator".to_string()]);
+    }
+
+    #[test]
+    fn overspending_block_fails_the_balance_check() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        let mut transaction = Transaction::new(validator.clone(), "alice".to_string(), 5000.0);
+        transaction.sign(signer.as_ref()).unwrap();
+        let block = next_block(&blockchain, &validator, vec![transaction]);
+        assert_eq!(failure_kind(blockchain.append_block(block)), Some(FailureKind::Balance));
+        assert_eq!(blockchain.get_account_balance(&validator), 1000.0);
+    }
+
+    #[test]
+    fn edited_accounts_fail_the_recorded_balance_check() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        assert_eq!(blockchain.validate_chain_detailed(), Ok(()));
+        blockchain.accounts.insert(validator, 1_000_000.0);
+        assert_eq!(
+            blockchain.validate_chain_detailed(),
+            Err(ValidationFailure { index: 0, kind: FailureKind::RecordedBalance })
+        );
+    }
+
+
+    #[test]
+    fn only_idle_empty_accounts_are_pruned() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let spender = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain.add_transaction(Transaction::new(validator.clone(), spender.clone(), 10.0), signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+        let signer = blockchain.keypairs[&spender].clone();
+        blockchain.add_transaction(Transaction::new(spender.clone(), "carol".to_string(), 10.0), signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+        blockchain.keypairs.remove(&spender);
+        blockchain.accounts.insert("dormant".to_string(), 0.0);
+        assert_eq!(blockchain.get_account_balance(&spender), 0.0);
+
+        // The spender emptied its account in the latest block, so only the never-used entry goes
+        assert_eq!(blockchain.prune_inactive_accounts(5), 1);
+        assert!(!blockchain.accounts.contains_key("dormant"));
+        assert!(blockchain.accounts.contains_key(&spender));
+
+        for _ in 0..6 {
+            let block = next_block(&blockchain, &validator, Vec::new());
+            blockchain.append_block(block).unwrap();
+        }
+        assert_eq!(blockchain.prune_inactive_accounts(5), 1);
+        assert!(!blockchain.accounts.contains_key(&spender));
+        assert!(blockchain.accounts.contains_key("carol"));
+        assert!(blockchain.accounts.contains_key(&validator));
+    }
+
+
+    #[test]
+    fn counterparties_tally_each_address_exchanged_with() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        for (recipient, amount) in [("alice", 10.0), ("bob", 7.0), ("alice", 5.0)] {
+            let transaction = Transaction::new(validator.clone(), recipient.to_string(), amount);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+            blockchain.create_block(&validator).unwrap();
+        }
+        let to_self = Transaction::new(validator.clone(), validator.clone(), 1.0);
+        blockchain.add_transaction(to_self, signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+
+        assert_eq!(
+            blockchain.counterparties(&validator),
+            vec![("alice".to_string(), 2, 15.0), ("bob".to_string(), 1, 7.0)]
+        );
+        assert_eq!(blockchain.counterparties("bob"), vec![(validator.clone(), 1, 7.0)]);
+        assert!(blockchain.counterparties("nobody").is_empty());
+    }
+
+
+    #[test]
+    fn parallel_and_sequential_signature_checks_agree() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        for amount in 1..=5 {
+            let transaction = Transaction::new(validator.clone(), "alice".to_string(), amount as f64);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+            blockchain.create_block(&validator).unwrap();
+        }
+        let sequential_first_invalid = |blockchain: &Blockchain| {
+            blockchain.chain.iter().skip(1).position(|block| {
+                !block.transactions.iter().all(|tx| blockchain.has_valid_signature(tx))
+            }).map(|position| position + 1)
+        };
+        assert_eq!(sequential_first_invalid(&blockchain), None);
+        #[cfg(feature = "parallel")]
+        assert_eq!(blockchain.first_invalid_signature(&blockchain.chain), None);
+        assert_eq!(blockchain.validate_chain_detailed(), Ok(()));
+
+        for index in [4, 2] {
+            blockchain.chain[index].transactions[0].amount += 100.0;
+            blockchain.chain[index].hash = blockchain.chain[index].calculate_hash();
+        }
+        assert_eq!(sequential_first_invalid(&blockchain), Some(2));
+        #[cfg(feature = "parallel")]
+        assert_eq!(blockchain.first_invalid_signature(&blockchain.chain), Some(2));
+        assert_eq!(
+            blockchain.validate_chain_detailed().map_err(|failure| failure.kind),
+            Err(FailureKind::Signature)
+        );
+    }
+
+
+    #[test]
+    fn balance_attestations_verify_only_while_they_match_the_chain() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        let attestation = blockchain.sign_balance_attestation(signer.as_ref()).unwrap();
+        assert_eq!((attestation.balance, attestation.nonce), (1000.0, 0));
+        assert_eq!(blockchain.verify_balance_attestation(&attestation), Ok(()));
+
+        let mut inflated = attestation.clone();
+        inflated.balance = 2000.0;
+        let err = blockchain.verify_balance_attestation(&inflated).unwrap_err();
+        assert!(err.contains("signature"), "{}", err);
+
+        let transaction = Transaction::new(validator.clone(), "alice".to_string(), 10.0);
+        blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+        let err = blockchain.verify_balance_attestation(&attestation).unwrap_err();
+        assert!(err.contains("does not match the on-chain balance"), "{}", err);
+
+        let current = blockchain.sign_balance_attestation(signer.as_ref()).unwrap();
+        assert_eq!((current.balance, current.nonce), (990.0, 1));
+        assert_eq!(blockchain.verify_balance_attestation(&current), Ok(()));
+    }
+
+
+    #[test]
+    fn rewards_are_locked_until_coinbase_maturity() {
+        let (mut blockchain, validator) = rewarding_chain(50.0);
+        let config = ChainConfig { coinbase_maturity: 3, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let signer = blockchain.keypairs[&validator].clone();
+        let block = next_block(&blockchain, &validator, vec![minted(&validator, 50.0)]);
+        blockchain.append_block(block).unwrap();
+        assert_eq!(blockchain.get_account_balance(&validator), 1050.0);
+
+        let spend_reward = || Transaction::new(validator.clone(), "alice".to_string(), 1040.0);
+        for _ in 0..2 {
+            assert_eq!(blockchain.spendable_balance(&validator), 1000.0);
+            assert_eq!(
+                blockchain.add_transaction(spend_reward(), signer.as_ref()),
+                Err(BlockchainError::InsufficientBalance)
+            );
+            let block = next_block(&blockchain, &validator, Vec::new());
+            blockchain.append_block(block).unwrap();
+        }
+        assert_eq!(blockchain.immature_rewards(&validator), 50.0);
+        assert!(blockchain.add_transaction(spend_reward(), signer.as_ref()).is_err());
+
+        let block = next_block(&blockchain, &validator, Vec::new());
+        blockchain.append_block(block).unwrap();
+        assert_eq!(blockchain.spendable_balance(&validator), 1050.0);
+        assert_eq!(blockchain.add_transaction(spend_reward(), signer.as_ref()), Ok(()));
+    }
+
+
+    #[test]
+    fn mining_empty_blocks_advances_the_height_in_dev_mode_only() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        blockchain.dev_mode = false;
+        assert_eq!(blockchain.mine_empty_blocks(5, &validator).err(), Some(BlockchainError::DevModeRequired));
+        assert_eq!(blockchain.chain.len(), 1);
+
+        blockchain.dev_mode = true;
+        let mined = blockchain.mine_empty_blocks(5, &validator).unwrap();
+        assert_eq!(mined.len(), 5);
+        assert_eq!(blockchain.get_latest_block().index, 5);
+        assert_eq!(blockchain.get_account_balance(&validator), 1025.0);
+        assert_eq!(blockchain.validator_earnings(&validator), (25.0, 0.0));
+        assert!(blockchain.validate_chain());
+
+        let second = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.add_validator(second.clone()).unwrap();
+        let config = ChainConfig { consensus_mode: ConsensusMode::RoundRobin, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let producer = blockchain.next_block_producer().unwrap();
+        let waiting = if producer == validator { second } else { validator };
+        assert_eq!(
+            blockchain.mine_empty_blocks(1, &waiting).err(),
+            Some(BlockchainError::NotProducerTurn { producer: producer.clone() })
+        );
+        assert_eq!(blockchain.mine_empty_blocks(1, &producer).unwrap().len(), 1);
+    }
+
+
+    #[test]
+    fn pending_transfers_outspent_by_a_block_are_reported_invalid() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain.add_transaction(Transaction::new(validator.clone(), "alice".to_string(), 600.0), signer.as_ref()).unwrap();
+        blockchain.add_transaction(Transaction::new(validator.clone(), "carol".to_string(), 100.0), signer.as_ref()).unwrap();
+        assert!(blockchain.pending_validity().iter().all(|(_, result)| result.is_ok()));
+
+        // The same key spends elsewhere, and the block reaches this node before the pool is mined
+        let mut elsewhere = Transaction::new(validator.clone(), "bob".to_string(), 500.0);
+        elsewhere.sign(signer.as_ref()).unwrap();
+        let block = next_block(&blockchain, &validator, vec![elsewhere]);
+        blockchain.append_block(block).unwrap();
+
+        assert_eq!(
+            blockchain.pending_validity(),
+            vec![(0, Err(BlockchainError::InsufficientBalance)), (1, Ok(()))]
+        );
+        let block = blockchain.create_block(&validator).unwrap();
+        assert_eq!(block.transactions.len(), 1);
+        assert_eq!(block.transactions[0].recipient, "carol");
+        assert!(blockchain.pending_transactions.is_empty());
+    }
+
+
+    #[test]
+    fn validation_is_quiet_unless_enabled_per_chain() {
+        let (blockchain, _) = rewarding_chain(0.0);
+        assert!(!blockchain.verbose_validation);
+        assert!(!copy_of(&blockchain).verbose_validation);
+
+        let mut verbose = copy_of(&blockchain);
+        verbose.verbose_validation = true;
+        assert!(verbose.validate_chain());
+        assert!(!blockchain.verbose_validation);
+    }
+
+
+    #[test]
+    fn tampered_genesis_amount_no_longer_matches_the_recorded_balance() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        assert!(blockchain.recorded_balance_mismatches().is_empty());
+
+        let genesis = &mut blockchain.chain[0];
+        let credit = genesis.transactions.iter_mut().find(|tx| tx.recipient == validator).unwrap();
+        credit.amount = 5000.0;
+        genesis.hash = genesis.calculate_hash();
+
+        assert_eq!(blockchain.recorded_balance_mismatches(), vec![validator.clone()]);
+        assert_eq!(
+            blockchain.validate_chain_detailed(),
+            Err(ValidationFailure { index: 0, kind: FailureKind::RecordedBalance })
+        );
+        assert_eq!(blockchain.get_account_balance(&validator), 1000.0);
+    }
+
+
+    #[test]
+    fn loaded_keypairs_without_a_balance_get_a_zero_entry() {
+        let dir = save_dir("account-entries");
+        let (chain_file, accounts_file) = (path_in(&dir, "chain.json"), path_in(&dir, "accounts.json"));
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let holder = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+
+        // Stands in for a chain file written before keypairs were given balance entries
+        let mut saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&chain_file).unwrap()).unwrap();
+        saved["accounts"].as_object_mut().unwrap().remove(&holder);
+        fs::write(&chain_file, saved.to_string()).unwrap();
+        assert!(!Blockchain::load_read_only(&chain_file).unwrap().accounts.contains_key(&holder));
+
+        let loaded = Blockchain::load_from_file(&chain_file, &accounts_file, None).unwrap();
+        assert_eq!(loaded.accounts.get(&holder), Some(&0.0));
+        assert_eq!(loaded.accounts.get(&validator), Some(&1000.0));
+        assert!(loaded.recorded_balance_mismatches().is_empty());
+    }
+
+
+    #[test]
+    fn unfunded_senders_get_a_clear_error_under_the_policy() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let newcomer = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&newcomer].clone();
+        let spend = || Transaction::new(newcomer.clone(), "alice".to_string(), 1.0);
+        assert_eq!(blockchain.add_transaction(spend(), signer.as_ref()), Err(BlockchainError::InsufficientBalance));
+
+        let config = ChainConfig { reject_unfunded_senders: true, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        assert_eq!(
+            blockchain.add_transaction(spend(), signer.as_ref()),
+            Err(BlockchainError::UnfundedSender { address: newcomer.clone() })
+        );
+
+        // Genesis allocations count as funding, and so does a confirmed transfer
+        let funder = blockchain.keypairs[&validator].clone();
+        blockchain.add_transaction(Transaction::new(validator.clone(), newcomer.clone(), 5.0), funder.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+        assert_eq!(blockchain.add_transaction(spend(), signer.as_ref()), Ok(()));
+    }
+
+
+    #[test]
+    fn ndjson_round_trip_keeps_hashes_and_balances() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        let mut replica = copy_of(&blockchain);
+        let signer = blockchain.keypairs[&validator].clone();
+        for (recipient, amount) in [("alice", 10.0), ("bob", 20.0)] {
+            let transaction = Transaction::new(validator.clone(), recipient.to_string(), amount).with_fee(0.5);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+            blockchain.create_block(&validator).unwrap();
+        }
+
+        let mut exported = Vec::new();
+        assert_eq!(blockchain.export_ndjson(&mut exported).unwrap(), 3);
+        assert_eq!(exported.iter().filter(|byte| **byte == b'\n').count(), 3);
+
+        assert_eq!(replica.import_ndjson(exported.as_slice()).unwrap(), 2);
+        assert_eq!(replica.fingerprint(), blockchain.fingerprint());
+        let hashes = |chain: &Blockchain| chain.chain.iter().map(|block| block.hash.clone()).collect::<Vec<_>>();
+        assert_eq!(hashes(&replica), hashes(&blockchain));
+        for address in [validator.as_str(), "alice", "bob"] {
+            assert_eq!(replica.get_account_balance(address), blockchain.get_account_balance(address));
+        }
+
+        // Importing again appends nothing
+        assert_eq!(replica.import_ndjson(exported.as_slice()).unwrap(), 0);
+    }
+
+
+    #[test]
+    fn round_robin_producers_rotate_with_the_height() {
+        let (mut blockchain, first) = rewarding_chain(0.0);
+        assert_eq!(blockchain.next_block_producer(), None);
+        let mut validators = vec![first];
+        for _ in 0..2 {
+            let address = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+            blockchain.add_validator(address.clone()).unwrap();
+            validators.push(address);
+        }
+        validators.sort();
+        let config = ChainConfig { consensus_mode: ConsensusMode::RoundRobin, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+
+        for height in 1..=6 {
+            let expected = &validators[height % validators.len()];
+            assert_eq!(blockchain.next_block_producer().as_ref(), Some(expected), "height {}", height);
+            let block = next_block(&blockchain, expected, Vec::new());
+            blockchain.append_block(block).unwrap();
+        }
+    }
+
+
+    #[test]
+    fn transfers_to_the_minting_address_are_rejected() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        let burn = Transaction::new(validator.clone(), "0".to_string(), 10.0);
+        assert_eq!(blockchain.simulate(&burn), Err(BlockchainError::ReservedRecipient));
+        assert_eq!(blockchain.add_transaction(burn.clone(), signer.as_ref()), Err(BlockchainError::ReservedRecipient));
+        assert!(blockchain.pending_transactions.is_empty());
+        assert_eq!(blockchain.get_account_balance(&validator), 1000.0);
+
+        // One that reached the pool some other way is dropped rather than mined
+        let mut smuggled = burn;
+        smuggled.sign(signer.as_ref()).unwrap();
+        blockchain.pending_transactions.push(smuggled);
+        assert_eq!(blockchain.pending_validity(), vec![(0, Err(BlockchainError::ReservedRecipient))]);
+    }
+
+
+    #[test]
+    fn blocks_between_keeps_only_the_inclusive_range() {
+        let mut blockchain = Blockchain::new("genesis");
+        blockchain.chain[0].timestamp = 0;
+        for timestamp in [1_000, 1_500, 2_000, 2_001] {
+            let block = block_at(&blockchain, timestamp);
+            blockchain.chain.push(block);
+        }
+
+        let indices: Vec<u32> = blockchain.blocks_between(1_000, 2_000).iter().map(|block| block.index).collect();
+        assert_eq!(indices, vec![1, 2, 3]);
+        assert_eq!(blockchain.blocks_between(0, 0).len(), 1);
+        assert!(blockchain.blocks_between(2_500, 3_000).is_empty());
+        assert!(blockchain.blocks_between(2_000, 1_000).is_empty());
+    }
+
+
+    #[test]
+    fn saves_are_retried_through_transient_failures() {
+        let flaky = |failures: u32| {
+            let mut attempts = 0;
+            move || {
+                attempts += 1;
+                if attempts <= failures {
+                    Err(std::io::Error::new(std::io::ErrorKind::WouldBlock, "file busy"))
+                } else {
+                    Ok(attempts)
+                }
+            }
+        };
+        let retry = SaveRetry { retries: 3, backoff: Duration::from_millis(1) };
+        assert_eq!(retry.run(flaky(2)).unwrap(), 3);
+        assert_eq!(retry.run(flaky(4)).unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+
+        let no_retry = SaveRetry { retries: 0, backoff: Duration::ZERO };
+        assert!(no_retry.run(flaky(1)).is_err());
+        assert_eq!(no_retry.run(flaky(0)).unwrap(), 1);
+    }
+
+
+    #[test]
+    fn keypairs_that_do_not_sign_for_their_address_fail_verification() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        assert!(blockchain.verify_keypair(&validator));
+        assert!(!blockchain.verify_keypair("unknown"));
+
+        // Another key filed under this address, as a corrupted accounts file would hold it
+        let other = Keypair::generate(&mut rand::rngs::OsRng);
+        blockchain.keypairs.insert(validator.clone(), Arc::new(other));
+        assert!(!blockchain.verify_keypair(&validator));
+
+        // A secret half paired with the wrong public half cannot sign for either address
+        let (first, second) = (Keypair::generate(&mut rand::rngs::OsRng), Keypair::generate(&mut rand::rngs::OsRng));
+        let mut spliced = first.secret.to_bytes().to_vec();
+        spliced.extend_from_slice(second.public.as_bytes());
+        let spliced = Keypair::from_bytes(&spliced).unwrap();
+        let address = second.verifier().address();
+        blockchain.keypairs.insert(address.clone(), Arc::new(spliced));
+        assert!(!blockchain.verify_keypair(&address));
+    }
+
+
+    #[test]
+    fn pending_transactions_group_by_sender() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let second = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain.add_transaction(Transaction::new(validator.clone(), second.clone(), 100.0), signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+
+        for (recipient, amount, fee) in [("alice", 10.0, 1.0), ("bob", 20.0, 0.5)] {
+            let transaction = Transaction::new(validator.clone(), recipient.to_string(), amount).with_fee(fee);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+        let second_signer = blockchain.keypairs[&second].clone();
+        blockchain.add_transaction(Transaction::new(second.clone(), "carol".to_string(), 5.0), second_signer.as_ref()).unwrap();
+
+        let grouped = blockchain.pending_by_sender();
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[&validator].len(), 2);
+        assert_eq!(grouped[&second].len(), 1);
+        let total = |sender: &str| {
+            let transactions: Vec<Transaction> = grouped[sender].iter().map(|tx| (*tx).clone()).collect();
+            blockchain.required_balance(&transactions)
+        };
+        assert_eq!(total(&validator), 31.5);
+        assert_eq!(total(&second), 5.0);
+    }
+
+
+    #[test]
+    fn min_balance_history_reports_the_trough_and_survives_reloads() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let saver = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain.add_transaction(Transaction::new(validator.clone(), saver.clone(), 900.0), signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+        let saver_signer = blockchain.keypairs[&saver].clone();
+        blockchain.add_transaction(Transaction::new(saver.clone(), validator.clone(), 500.0), saver_signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+
+        assert_eq!(blockchain.get_account_balance(&validator), 600.0);
+        assert_eq!(blockchain.min_balance_history(&validator), 100.0);
+        // The saver first appears with 900, so the empty balance before that does not count
+        assert_eq!(blockchain.min_balance_history(&saver), 400.0);
+        assert_eq!(blockchain.account_stats(&saver).min_balance, 400.0);
+        assert_eq!(blockchain.min_balance_history("nobody"), 0.0);
+
+        let reloaded = copy_of(&blockchain);
+        for address in [&validator, &saver] {
+            assert_eq!(reloaded.min_balance_history(address), blockchain.min_balance_history(address));
+        }
+    }
+
+
+    #[test]
+    fn minting_transactions_cannot_be_signed_or_submitted() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+
+        let mut minting = minted(&validator, 10.0);
+        let sign_error = minting.sign(signer.as_ref()).unwrap_err();
+        assert!(sign_error.contains("cannot be signed"), "{}", sign_error);
+        assert!(minting.signature.is_none());
+
+        assert_eq!(blockchain.add_transaction(minting.clone(), signer.as_ref()), Err(BlockchainError::ReservedSender));
+        assert_eq!(blockchain.submit_signed_transaction(minting), Err(BlockchainError::ReservedSender));
+        let submit_error = BlockchainError::ReservedSender.to_string();
+        assert!(submit_error.contains("cannot be submitted"), "{}", submit_error);
+        assert_ne!(sign_error, submit_error);
+        assert!(blockchain.pending_transactions.is_empty());
+    }
+
+
+    #[test]
+    fn batch_promotion_reports_unregistered_addresses() {
+        let (mut blockchain, _) = rewarding_chain(0.0);
+        let first = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let second = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+        let addresses = vec![first.clone(), "stranger".to_string(), second.clone()];
+        let unregistered = Err(BlockchainError::UnregisteredAddress { address: "stranger".to_string() });
+
+        assert_eq!(blockchain.add_validators(&addresses, true), vec![Ok(()), unregistered.clone(), Ok(())]);
+        assert!(!blockchain.is_validator(&first) && !blockchain.is_validator(&second));
+
+        assert_eq!(blockchain.add_validators(&addresses, false), vec![Ok(()), unregistered, Ok(())]);
+        assert!(blockchain.is_validator(&first) && blockchain.is_validator(&second));
+        assert!(!blockchain.validators.contains_key("stranger"));
+    }
+
+
+    #[test]
+    fn next_block_revenue_is_the_selected_fees_plus_the_reward() {
+        let (mut blockchain, validator) = rewarding_chain(5.0);
+        assert_eq!(blockchain.next_block_revenue(), 0.0);
+        let config = ChainConfig { max_block_transactions: 2, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let signer = blockchain.keypairs[&validator].clone();
+        for (recipient, fee) in [("alice", 1.0), ("bob", 2.5), ("carol", 0.5)] {
+            let transaction = Transaction::new(validator.clone(), recipient.to_string(), 10.0).with_fee(fee);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+
+        assert_eq!(blockchain.next_block_revenue(), 2.5 + 1.0 + 5.0);
+        let block = blockchain.preview_block(&validator).unwrap();
+        let fees: f64 = block.transactions.iter().map(|tx| tx.fee).sum();
+        let reward: f64 = block.transactions.iter().filter(|tx| tx.sender == "0").map(|tx| tx.amount).sum();
+        assert_eq!(blockchain.next_block_revenue(), fees + reward);
+    }
+
+
+    #[test]
+    fn low_fee_transactions_in_a_full_mempool_are_stuck_until_bumped() {
+        let (mut blockchain, validator) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&validator].clone();
+        for (recipient, fee) in [("alice", 1.0), ("bob", 2.0), ("carol", 0.1)] {
+            let transaction = Transaction::new(validator.clone(), recipient.to_string(), 10.0).with_fee(fee);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+        }
+        assert!(blockchain.stuck_transactions(1).is_empty());
+
+        let config = ChainConfig { max_block_transactions: 2, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let stuck: Vec<Transaction> = blockchain.stuck_transactions(1).into_iter().cloned().collect();
+        assert_eq!(stuck.len(), 1);
+        assert_eq!(stuck[0].recipient, "carol");
+        assert!(blockchain.stuck_transactions(2).is_empty());
+
+        let fee = blockchain.suggested_fee(&stuck[0], 1).unwrap();
+        assert!(fee > 1.0 && fee < 2.0, "{}", fee);
+        let mut bumped = stuck[0].clone().with_fee(fee);
+        bumped.sign(signer.as_ref()).unwrap();
+        blockchain.pending_transactions.retain(|tx| tx.recipient != "carol");
+        blockchain.pending_transactions.push(bumped);
+        let stuck = blockchain.stuck_transactions(1);
+        assert_eq!(stuck.len(), 1);
+        assert_eq!(stuck[0].recipient, "alice");
+    }
+
+
+    #[test]
+    fn stake_weighted_producers_survive_save_and_load() {
+        let dir = save_dir("consensus-seed");
+        let (chain_file, accounts_file) = (path_in(&dir, "chain.json"), path_in(&dir, "accounts.json"));
+        let (mut blockchain, first) = rewarding_chain(0.0);
+        let signer = blockchain.keypairs[&first].clone();
+        for amount in [300.0, 50.0] {
+            let address = blockchain.register_keypair(Keypair::generate(&mut rand::rngs::OsRng));
+            blockchain.add_validator(address.clone()).unwrap();
+            blockchain.add_transaction(Transaction::new(first.clone(), address, amount), signer.as_ref()).unwrap();
+        }
+        blockchain.create_block(&first).unwrap();
+        let config = ChainConfig { consensus_mode: ConsensusMode::StakeWeighted, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+
+        for _ in 0..5 {
+            blockchain.save_to_file(&chain_file, &accounts_file).unwrap();
+            let loaded = Blockchain::load_from_file(&chain_file, &accounts_file, None).unwrap();
+            assert_eq!(loaded.consensus_seed(), blockchain.consensus_seed());
+            let producer = blockchain.next_block_producer().unwrap();
+            assert_eq!(loaded.next_block_producer(), Some(producer.clone()));
+
+            let block = next_block(&blockchain, &producer, Vec::new());
+            blockchain.append_block(block).unwrap();
+        }
+    }
+}
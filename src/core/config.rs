@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+
+/// How the producer of the next block is chosen among active validators
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ConsensusMode {
+    /// Any active validator may produce the next block
+    #[default]
+    Open,
+    /// Active validators take turns in address order
+    RoundRobin,
+    /// Producers are drawn with probability proportional to their balance
+    StakeWeighted,
+}
+
+/// What `add_transaction` does when the mempool is at capacity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum MempoolPolicy {
+    /// Reject the incoming transaction
+    #[default]
+    RejectNew,
+    /// Evict the lowest-fee pending transaction if the newcomer pays more
+    EvictLowestFee,
+}
+
+/// Policy parameters of a chain, persisted with it
+///
+/// Every parameter is optional in the stored file, so chains saved before a
+/// parameter existed load with its default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChainConfig {
+    /// Minimum amount a transfer must carry (0 disables the check)
+    pub min_transfer: f64,
+    /// Maximum number of transactions per block (0 means unlimited)
+    pub max_block_transactions: usize,
+    /// Deepest reorg accepted by `try_replace_chain` (None means unlimited)
+    pub max_reorg_depth: Option<usize>,
+    /// Amount credited to the validator of each new block
+    pub block_reward: f64,
+    /// Blocks that must follow a reward's block before the reward can be spent (0 disables the lock)
+    pub coinbase_maturity: u32,
+    /// Largest fee a transaction may carry (None means no cap)
+    pub max_fee: Option<f64>,
+    /// Reject transfers that carry no memo
+    pub require_memo: bool,
+    /// Reject senders with no balance that have never received a confirmed transfer
+    pub reject_unfunded_senders: bool,
+    /// Largest amount a single transfer may move (None means no cap)
+    pub max_transaction_amount: Option<f64>,
+    pub consensus_mode: ConsensusMode,
+    /// Select transactions sent by validators ahead of other pending transactions
+    pub prioritize_validator_transactions: bool,
+    /// Blocks with fewer transactions than this need confirmation in the CLI (0 disables it)
+    pub min_block_transactions_warn: usize,
+    /// Maximum number of pending transactions (0 means unlimited)
+    pub max_mempool_size: usize,
+    pub mempool_policy: MempoolPolicy,
+    /// Transfers above this amount are held until the recipient claims them (None disables holds)
+    pub claim_threshold: Option<f64>,
+    /// Blocks after which an unclaimed transfer returns to its sender
+    pub claim_expiry_blocks: u32,
+    /// How many recent transaction hashes to remember for deduplication (0 disables it)
+    pub seen_cache_size: usize,
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        ChainConfig {
+            min_transfer: 0.0,
+            max_block_transactions: 0,
+            max_reorg_depth: None,
+            block_reward: 0.0,
+            max_fee: None,
+            require_memo: false,
+            max_transaction_amount: None,
+            consensus_mode: ConsensusMode::Open,
+            prioritize_validator_transactions: false,
+            min_block_transactions_warn: 0,
+            max_mempool_size: 0,
+            mempool_policy: MempoolPolicy::RejectNew,
+            seen_cache_size: 1000,
+            claim_threshold: None,
+            claim_expiry_blocks: 100,
+            coinbase_maturity: 0,
+            reject_unfunded_senders: false,
+        }
+    }
+}
+
+impl ChainConfig {
+    /// Loads a config from a JSON file; parameters it leaves out take their defaults
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|_| format!("Failed to read chain config: {}", path))?;
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse chain config: {}", e))
+    }
+
+    /// Checks that every amount is a finite number in its allowed range
+    pub fn validate(&self) -> Result<(), String> {
+        let non_negative = [
+            ("min_transfer", Some(self.min_transfer)),
+            ("block_reward", Some(self.block_reward)),
+            ("max_fee", self.max_fee),
+        ];
+        for (name, value) in non_negative {
+            if value.is_some_and(|value| !value.is_finite() || value < 0.0) {
+                return Err(format!("{} must be a finite amount of at least 0", name));
+            }
+        }
+        let positive = [
+            ("max_transaction_amount", self.max_transaction_amount),
+            ("claim_threshold", self.claim_threshold),
+        ];
+        for (name, value) in positive {
+            if value.is_some_and(|value| !value.is_finite() || value <= 0.0) {
+                return Err(format!("{} must be a finite amount above 0", name));
+            }
+        }
+        if self.max_transaction_amount.is_some_and(|max| max < self.min_transfer) {
+            return Err("max_transaction_amount is below min_transfer, so no transfer could succeed".to_string());
+        }
+        if self.claim_threshold.is_some() && self.claim_expiry_blocks == 0 {
+            return Err("claim_expiry_blocks must be at least 1 while claims are enabled".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Formats optional limits, showing None as the given word
+fn or_none<T: fmt::Display>(value: &Option<T>, none: &str) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => none.to_string(),
+    }
+}
+
+// Formats counts where 0 turns the limit off
+fn or_zero(value: usize, zero: &str) -> String {
+    if value == 0 {
+        zero.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for ChainConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Consensus mode: {:?}", self.consensus_mode)?;
+        writeln!(f, "Block reward: {:.2}", self.block_reward)?;
+        writeln!(f, "Coinbase maturity: {}", or_zero(self.coinbase_maturity as usize, "off"))?;
+        writeln!(f, "Max block transactions: {}", or_zero(self.max_block_transactions, "unlimited"))?;
+        writeln!(f, "Min block transactions warning: {}", or_zero(self.min_block_transactions_warn, "off"))?;
+        writeln!(f, "Prioritize validator transactions: {}", self.prioritize_validator_transactions)?;
+        writeln!(f, "Min transfer: {:.2}", self.min_transfer)?;
+        writeln!(f, "Max transaction amount: {}", or_none(&self.max_transaction_amount, "unlimited"))?;
+        writeln!(f, "Max fee: {}", or_none(&self.max_fee, "unlimited"))?;
+        writeln!(f, "Require memo: {}", self.require_memo)?;
+        writeln!(f, "Reject unfunded senders: {}", self.reject_unfunded_senders)?;
+        writeln!(f, "Claim threshold: {}", or_none(&self.claim_threshold, "off"))?;
+        writeln!(f, "Claim expiry: {} blocks", self.claim_expiry_blocks)?;
+        writeln!(f, "Max mempool size: {}", or_zero(self.max_mempool_size, "unlimited"))?;
+        writeln!(f, "Mempool policy: {:?}", self.mempool_policy)?;
+        writeln!(f, "Seen transaction cache: {}", or_zero(self.seen_cache_size, "off"))?;
+        write!(f, "Max reorg depth: {}", or_none(&self.max_reorg_depth, "unlimited"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert!(ChainConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn out_of_range_amounts_are_rejected() {
+        let invalid = [
+            ChainConfig { block_reward: -1.0, ..ChainConfig::default() },
+            ChainConfig { min_transfer: f64::NAN, ..ChainConfig::default() },
+            ChainConfig { max_fee: Some(f64::INFINITY), ..ChainConfig::default() },
+            ChainConfig { max_transaction_amount: Some(0.0), ..ChainConfig::default() },
+            ChainConfig { min_transfer: 5.0, max_transaction_amount: Some(1.0), ..ChainConfig::default() },
+            ChainConfig { claim_threshold: Some(10.0), claim_expiry_blocks: 0, ..ChainConfig::default() },
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{:?}", config);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::core::blockchain::ValidationFailure;
+use std::fmt;
+
+/// Errors returned by blockchain operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockchainError {
+    InsufficientBalance,
+    DustAmount { amount: f64, minimum: f64 },
+    FeeTooHigh { fee: f64, maximum: f64 },
+    AmountTooLarge { amount: f64, maximum: f64 },
+    AmountOutOfRange { amount: f64 },
+    MemoRequired,
+    ReservedRecipient,
+    ReservedSender,
+    UnfundedSender { address: String },
+    UnknownClaim { tx_hash: String },
+    UnregisteredAddress { address: String },
+    BalanceOverflow { address: String },
+    SigningFailed(String),
+    InvalidSignature,
+    DuplicateTransaction,
+    MempoolFull { capacity: usize },
+    NotValidator,
+    NotProducerTurn { producer: String },
+    DevModeRequired,
+    NoPendingTransactions,
+    NoValidTransactions,
+    InvalidBlock(ValidationFailure),
+}
+
+impl fmt::Display for BlockchainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockchainError::InsufficientBalance => {
+                write!(f, "Insufficient balance for transaction")
+            }
+            BlockchainError::DustAmount { amount, minimum } => write!(
+                f,
+                "Transfer amount {:.2} is below the minimum transfer of {:.2}",
+                amount, minimum
+            ),
+            BlockchainError::FeeTooHigh { fee, maximum } => write!(
+                f,
+                "Transaction fee {:.2} exceeds the maximum fee of {:.2}",
+                fee, maximum
+            ),
+            BlockchainError::AmountTooLarge { amount, maximum } => write!(
+                f,
+                "Transfer amount {:.2} exceeds the maximum transaction amount of {:.2}",
+                amount, maximum
+            ),
+            BlockchainError::AmountOutOfRange { amount } => write!(
+                f,
+                "Amount {} is not a non-negative number the chain can represent exactly",
+                amount
+            ),
+            BlockchainError::MemoRequired => write!(f, "This chain requires a memo on every transfer"),
+            BlockchainError::ReservedRecipient => {
+                write!(f, "Address \"0\" is reserved for minting and cannot receive transfers")
+            }
+            BlockchainError::ReservedSender => write!(
+                f,
+                "Transactions from the minting address \"0\" are created by blocks and cannot be submitted"
+            ),
+            BlockchainError::UnfundedSender { address } => write!(
+                f,
+                "Sender {} has never been funded; send it coins before spending from it",
+                address
+            ),
+            BlockchainError::UnknownClaim { tx_hash } => {
+                write!(f, "No held transfer {} is waiting for this claimant", tx_hash)
+            }
+            BlockchainError::UnregisteredAddress { address } => {
+                write!(f, "Address {} is not registered", address)
+            }
+            BlockchainError::BalanceOverflow { address } => write!(
+                f,
+                "Balance of {} would exceed the largest exactly representable amount",
+                address
+            ),
+            BlockchainError::SigningFailed(reason) => {
+                write!(f, "Failed to sign transaction: {}", reason)
+            }
+            BlockchainError::InvalidSignature => {
+                write!(f, "Transaction signature is missing or does not match the sender")
+            }
+            BlockchainError::DuplicateTransaction => {
+                write!(f, "Transaction is already pending or confirmed")
+            }
+            BlockchainError::MempoolFull { capacity } => write!(
+                f,
+                "Mempool is full ({} transactions) and the fee is too low to replace any",
+                capacity
+            ),
+            BlockchainError::NotValidator => write!(f, "Only authorized validators can create blocks"),
+            BlockchainError::NotProducerTurn { producer } => {
+                write!(f, "It is {}'s turn to produce the next block", producer)
+            }
+            BlockchainError::DevModeRequired => write!(f, "This is a developer operation; enable dev mode first"),
+            BlockchainError::NoPendingTransactions => {
+                write!(f, "No pending transactions to include in block")
+            }
+            BlockchainError::NoValidTransactions => {
+                write!(f, "No valid pending transactions to include in block")
+            }
+            BlockchainError::InvalidBlock(failure) => write!(f, "Invalid block: {}", failure),
+        }
+    }
+}
+
+impl std::error::Error for BlockchainError {}
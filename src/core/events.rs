@@ -0,0 +1,136 @@
+use crate::core::ChainConfig;
+use crate::models::{Block, Transaction};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// A change made to a blockchain, recorded so the same state can be rebuilt elsewhere
+///
+/// Logs never contain signing keys: the starting state is the chain file's contents
+/// and accounts are identified by address only.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ChainEvent {
+    /// The persisted state the following events apply to
+    Started(Box<serde_json::Value>),
+    TransactionAdded(Transaction),
+    /// Pending transactions removed because they were no longer valid
+    PendingDropped(Vec<Transaction>),
+    BlockAdded(Block),
+    /// The whole chain after a longer candidate replaced it
+    ChainReplaced(Vec<Block>),
+    ChainRehashed,
+    AccountRegistered(String),
+    AccountsPruned(Vec<String>),
+    ValidatorAdded(String),
+    ValidatorTransferred { from: String, to: String },
+    ConfigChanged(ChainConfig),
+}
+
+/// Writes events as newline-delimited JSON, one event per line
+pub fn write_event_log(path: &str, events: &[ChainEvent]) -> Result<(), String> {
+    let mut contents = String::new();
+    for event in events {
+        let line = serde_json::to_string(event).map_err(|e| format!("Failed to serialize event: {}", e))?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+    fs::write(path, contents).map_err(|e| format!("Failed to write event log: {}", e))
+}
+
+/// Reads an event log written by `write_event_log`
+pub fn read_event_log(path: &str) -> Result<Vec<ChainEvent>, String> {
+    let contents = fs::read_to_string(path).map_err(|_| format!("Failed to read event log: {}", path))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_number, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| format!("Failed to parse event on line {}: {}", line_number + 1, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Blockchain, ConsensusMode};
+    use crate::models::signature::Signer;
+    use ed25519_dalek::Keypair;
+    use rand::rngs::OsRng;
+    use std::process;
+
+    #[test]
+    fn replaying_a_written_log_rebuilds_the_same_state() {
+        let keypair = Keypair::generate(&mut OsRng);
+        let admin = keypair.verifier().address();
+        let mut blockchain = Blockchain::new(&admin);
+        blockchain.register_keypair(keypair);
+        blockchain.add_validator(admin.clone()).unwrap();
+        blockchain.dev_mode = true;
+        blockchain.record_events(Vec::new()).unwrap();
+
+        let second = blockchain.register_keypair(Keypair::generate(&mut OsRng));
+        blockchain.add_validator(second.clone()).unwrap();
+        let config = ChainConfig { consensus_mode: ConsensusMode::RoundRobin, ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let signer = blockchain.keypairs[&admin].clone();
+        blockchain
+            .add_transaction(Transaction::new(admin.clone(), second, 25.0).with_fee(0.5), signer.as_ref())
+            .unwrap();
+        let producer = blockchain.next_block_producer().unwrap();
+        blockchain.create_block(&producer).unwrap();
+        // Replay must accept dev-mined blocks too
+        for _ in 0..2 {
+            let producer = blockchain.next_block_producer().unwrap();
+            blockchain.mine_empty_blocks(1, &producer).unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("blockchain-events-{}.ndjson", process::id()));
+        let path = path.to_string_lossy();
+        write_event_log(&path, blockchain.event_log().unwrap()).unwrap();
+        let replayed = Blockchain::replay(&read_event_log(&path).unwrap()).unwrap();
+
+        assert_eq!(replayed.chain.len(), 4);
+        assert_eq!(replayed.fingerprint(), blockchain.fingerprint());
+        assert_eq!(replayed.state_root(), blockchain.state_root());
+        assert_eq!(replayed.config(), blockchain.config());
+    }
+
+
+    #[test]
+    fn replay_rejects_events_that_would_fail_their_original_checks() {
+        let keypair = Keypair::generate(&mut OsRng);
+        let admin = keypair.verifier().address();
+        let mut blockchain = Blockchain::new(&admin);
+        blockchain.register_keypair(keypair);
+        blockchain.add_validator(admin.clone()).unwrap();
+        blockchain.record_events(Vec::new()).unwrap();
+        let signer = blockchain.keypairs[&admin].clone();
+        blockchain.add_transaction(Transaction::new(admin.clone(), "alice".to_string(), 25.0), signer.as_ref()).unwrap();
+        blockchain.create_block(&admin).unwrap();
+        let events = blockchain.event_log().unwrap().to_vec();
+        assert!(Blockchain::replay(&events).is_ok());
+        let position = events.iter().position(|event| matches!(event, ChainEvent::TransactionAdded(_))).unwrap();
+
+        // An amount edited after signing
+        let mut edited = events.clone();
+        if let ChainEvent::TransactionAdded(tx) = &mut edited[position] {
+            tx.amount = 900.0;
+        }
+        let err = Blockchain::replay(&edited).err().unwrap();
+        assert!(err.starts_with(&format!("Event {} failed", position + 1)), "{}", err);
+
+        // A properly signed transfer the sender could never have afforded
+        let mut overspend = Transaction::new(admin.clone(), "alice".to_string(), 5000.0);
+        overspend.sign(signer.as_ref()).unwrap();
+        let mut inserted = events.clone();
+        inserted[position] = ChainEvent::TransactionAdded(overspend);
+        let err = Blockchain::replay(&inserted).err().unwrap();
+        assert!(err.contains("Insufficient balance"), "{}", err);
+
+        // The same transaction recorded twice
+        let mut repeated = events.clone();
+        repeated.insert(position, events[position].clone());
+        assert!(Blockchain::replay(&repeated).is_err());
+    }
+}
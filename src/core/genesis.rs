@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Rounding slack allowed when comparing allocations to the total supply
+const SUPPLY_TOLERANCE: f64 = 1e-9;
+
+/// Parameters used to create the genesis block of a new chain
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenesisConfig {
+    /// Accounts credited by the genesis block, in order
+    pub allocations: Vec<(String, f64)>,
+    /// Fixed genesis timestamp for reproducible chains (defaults to now)
+    pub timestamp: Option<u64>,
+    /// Addresses that start out as active validators
+    #[serde(default)]
+    pub validators: Vec<String>,
+    /// Fixed supply the allocations must add up to (None skips the check)
+    #[serde(default)]
+    pub total_supply: Option<f64>,
+    /// Receives whatever part of the total supply the allocations leave over
+    #[serde(default)]
+    pub treasury: Option<String>,
+}
+
+impl GenesisConfig {
+    /// Creates a config crediting a single address, which also becomes the first validator
+    pub fn single(address: &str, amount: f64) -> Self {
+        GenesisConfig {
+            allocations: vec![(address.to_string(), amount)],
+            timestamp: None,
+            validators: vec![address.to_string()],
+            total_supply: None,
+            treasury: None,
+        }
+    }
+
+    /// Loads a config from a JSON file
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|_| format!("Failed to read genesis config: {}", path))?;
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse genesis config: {}", e))
+    }
+
+    /// Returns the allocations to credit, checked against the declared total supply
+    ///
+    /// With a treasury, any remainder is allocated to it; otherwise the allocations
+    /// must match the supply exactly.
+    pub fn resolve_allocations(&self) -> Result<Vec<(String, f64)>, String> {
+        let mut allocations = self.allocations.clone();
+        if let Some((address, amount)) = allocations.iter().find(|(_, amount)| !amount.is_finite()) {
+            return Err(format!("Genesis allocation of {} to {} is not a finite amount", amount, address));
+        }
+        if let Some((address, amount)) = allocations.iter().find(|(_, amount)| *amount < 0.0) {
+            return Err(format!("Genesis allocation of {:.2} to {} is negative", amount, address));
+        }
+
+        let total_supply = match self.total_supply {
+            Some(total_supply) if !total_supply.is_finite() => {
+                return Err(format!("Genesis total supply of {} is not a finite amount", total_supply))
+            }
+            Some(total_supply) => total_supply,
+            None => return Ok(allocations),
+        };
+        let allocated: f64 = allocations.iter().map(|(_, amount)| amount).sum();
+        let remainder = total_supply - allocated;
+
+        if remainder < -SUPPLY_TOLERANCE {
+            return Err(format!(
+                "Genesis allocations of {:.2} exceed the total supply of {:.2}",
+                allocated, total_supply
+            ));
+        }
+        if remainder > SUPPLY_TOLERANCE {
+            match &self.treasury {
+                Some(treasury) => allocations.push((treasury.clone(), remainder)),
+                None => {
+                    return Err(format!(
+                        "Genesis allocations of {:.2} fall short of the total supply of {:.2} and no treasury is set",
+                        allocated, total_supply
+                    ))
+                }
+            }
+        }
+        Ok(allocations)
+    }
+
+    /// Returns the configured timestamp, or the current time if none was set
+    pub fn resolve_timestamp(&self) -> u64 {
+        self.timestamp.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_and_supply_must_be_finite() {
+        let valid = GenesisConfig {
+            total_supply: Some(1500.0),
+            treasury: Some("treasury".to_string()),
+            ..GenesisConfig::single("founder", 1000.0)
+        };
+        assert_eq!(
+            valid.resolve_allocations().unwrap(),
+            vec![("founder".to_string(), 1000.0), ("treasury".to_string(), 500.0)]
+        );
+
+        for amount in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let allocation = GenesisConfig { allocations: vec![("founder".to_string(), amount)], ..valid.clone() };
+            assert!(allocation.resolve_allocations().is_err(), "allocation {}", amount);
+            let supply = GenesisConfig { total_supply: Some(amount), ..valid.clone() };
+            assert!(supply.resolve_allocations().is_err(), "supply {}", amount);
+        }
+    }
+}
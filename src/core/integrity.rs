@@ -0,0 +1,123 @@
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Path of the sidecar file holding the accounts file's HMAC
+pub fn signature_path(accounts_file: &str) -> String {
+    format!("{}.sig", accounts_file)
+}
+
+/// The sidecar contents for an accounts file: an HMAC of its contents keyed by the passphrase
+pub fn accounts_signature(contents: &[u8], passphrase: &str) -> String {
+    hmac_sha256(passphrase.as_bytes(), contents)
+}
+
+/// Checks the accounts file against its sidecar HMAC
+///
+/// A missing sidecar is an error, since deleting it would otherwise bypass the check.
+/// `allow_unsigned` accepts it once, for enabling signing on an existing chain; the
+/// sidecar is then written on the next save.
+pub fn verify_accounts_file(accounts_file: &str, passphrase: &str, allow_unsigned: bool) -> Result<(), String> {
+    let sidecar = signature_path(accounts_file);
+    if !Path::new(&sidecar).exists() {
+        if !allow_unsigned {
+            return Err(format!(
+                "Accounts file {} has no signature ({}). Use --init-accounts-signature to sign it for the first time.",
+                accounts_file, sidecar
+            ));
+        }
+        println!("Warning: {} has no signature yet; it will be signed on the next save", accounts_file);
+        return Ok(());
+    }
+
+    let contents = fs::read(accounts_file)
+        .map_err(|_| format!("Failed to read accounts file: {}", accounts_file))?;
+    let expected = fs::read_to_string(&sidecar)
+        .map_err(|_| format!("Failed to read accounts signature: {}", sidecar))?;
+
+    if !constant_time_eq(hmac_sha256(passphrase.as_bytes(), &contents).as_bytes(), expected.trim().as_bytes()) {
+        return Err(format!(
+            "Accounts file {} was modified or the passphrase is wrong",
+            accounts_file
+        ));
+    }
+    Ok(())
+}
+
+/// Compares two byte strings without exiting early, so timing reveals nothing about where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// HMAC-SHA256 (RFC 2104) returned as hex
+fn hmac_sha256(key: &[u8], message: &[u8]) -> String {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut key_block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        key_block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        key_block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(key_block.map(|byte| byte ^ 0x36));
+    inner.update(message);
+
+    let mut outer = Sha256::new();
+    outer.update(key_block.map(|byte| byte ^ 0x5c));
+    outer.update(inner.finalize());
+    hex::encode(outer.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn accounts_path(name: &str) -> String {
+        let dir = env::temp_dir().join(format!("blockchain-integrity-{}-{}", name, process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("accounts.json").to_string_lossy().into_owned();
+        fs::write(&path, "{}").unwrap();
+        let _ = fs::remove_file(signature_path(&path));
+        path
+    }
+
+    #[test]
+    fn missing_signature_is_rejected_unless_allowed() {
+        let accounts = accounts_path("missing");
+        assert!(verify_accounts_file(&accounts, "secret", false).is_err());
+        assert!(verify_accounts_file(&accounts, "secret", true).is_ok());
+    }
+
+    #[test]
+    fn signed_file_verifies_until_modified() {
+        let accounts = accounts_path("signed");
+        fs::write(signature_path(&accounts), accounts_signature(b"{}", "secret")).unwrap();
+        assert!(verify_accounts_file(&accounts, "secret", false).is_ok());
+        assert!(verify_accounts_file(&accounts, "wrong", false).is_err());
+
+        fs::write(&accounts, "{\"tampered\": true}").unwrap();
+        assert!(verify_accounts_file(&accounts, "secret", false).is_err());
+    }
+
+    #[test]
+    fn hmac_matches_rfc_4231_test_case_2() {
+        assert_eq!(
+            hmac_sha256(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn constant_time_eq_compares_whole_inputs() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+}
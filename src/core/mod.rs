@@ -1,3 +1,13 @@
 pub mod blockchain;
+pub mod config;
+pub mod error;
+pub mod events;
+pub mod genesis;
+pub mod integrity;
+pub mod proof;
+pub mod wallet;
 
-pub use blockchain::Blockchain;
\ No newline at end of file
+pub use blockchain::Blockchain;
+pub use config::{ChainConfig, ConsensusMode, MempoolPolicy};
+pub use error::BlockchainError;
+pub use genesis::GenesisConfig;
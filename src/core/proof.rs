@@ -0,0 +1,304 @@
+use crate::models::merkle::{self, ProofStep};
+use crate::models::signature::{Signer, Verifier};
+use crate::models::BlockHeader;
+use ed25519_dalek::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Merkle leaf committing to one account's balance
+pub fn balance_leaf(address: &str, balance: f64) -> String {
+    format!("{}:{}", address, balance)
+}
+
+/// Leaves of a balances Merkle tree, one per account, sorted by address
+pub fn balance_leaves(balances: &HashMap<String, f64>) -> Vec<String> {
+    let sorted: BTreeMap<&String, &f64> = balances.iter().collect();
+    sorted
+        .into_iter()
+        .map(|(address, balance)| balance_leaf(address, *balance))
+        .collect()
+}
+
+/// Checks that a header chain starts at genesis, links up, and that every header after
+/// genesis is signed by its producer, who must be one of the given validators
+///
+/// Signatures cover the Merkle root rather than the transactions, so no block bodies are
+/// needed. The genesis header is the trust anchor and carries no signature: compare its
+/// hash with a genesis hash obtained from a trusted source.
+pub fn verify_header_chain(headers: &[BlockHeader], validators: &[String]) -> Result<(), String> {
+    let first = headers.first().ok_or_else(|| "Header chain is empty".to_string())?;
+    if first.index != 0 {
+        return Err(format!("Header chain starts at #{} instead of genesis", first.index));
+    }
+    for pair in headers.windows(2) {
+        if pair[1].index != pair[0].index + 1 {
+            return Err(format!("Header #{} follows header #{}", pair[1].index, pair[0].index));
+        }
+    }
+    check_header_links(headers)?;
+
+    for header in &headers[1..] {
+        if !validators.contains(&header.validator) {
+            return Err(format!("Header #{} was produced by {}, which is not a validator", header.index, header.validator));
+        }
+        let public_key = hex::decode(&header.validator)
+            .ok()
+            .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .ok_or_else(|| format!("Validator {} of header #{} is not a public key", header.validator, header.index))?;
+        if !header.verify_signature(&public_key) {
+            return Err(format!("Header #{} is not signed by its validator {}", header.index, header.validator));
+        }
+    }
+    Ok(())
+}
+
+fn check_header_links(headers: &[BlockHeader]) -> Result<(), String> {
+    for pair in headers.windows(2) {
+        if pair[1].previous_hash != pair[0].hash {
+            return Err(format!("Header #{} does not link to header #{}", pair[1].index, pair[0].index));
+        }
+    }
+    Ok(())
+}
+
+/// An account's balance at the tip, proven against the tip header's balances root
+///
+/// Headers commit to every balance after their block, so a single Merkle path proves
+/// the whole balance: no transaction can be left out, and held and refunded transfers
+/// count exactly as `get_account_balance` counts them. The validator's signature does
+/// not cover the balances root, so the tip must come from a source the verifier already trusts.
+#[derive(Clone)]
+pub struct BalanceProof {
+    pub address: String,
+    pub balance: f64,
+    /// Header of the block the balance is proven at
+    pub header: BlockHeader,
+    /// Path from the account's balance leaf to the header's balances root
+    pub proof: Vec<ProofStep>,
+}
+
+impl BalanceProof {
+    /// Checks that the proof is for the trusted tip and that its balances root commits to the balance
+    ///
+    /// Only the tip counts: a proof against an older header shows a balance that may
+    /// since have been spent.
+    pub fn verify(&self, trusted_headers: &[BlockHeader]) -> Result<(), String> {
+        let tip = trusted_headers
+            .last()
+            .ok_or_else(|| "No trusted headers to verify against".to_string())?;
+        if self.header != *tip {
+            return Err(format!(
+                "Proof is for header #{} but the trusted tip is header #{}",
+                self.header.index, tip.index
+            ));
+        }
+
+        let leaf = balance_leaf(&self.address, self.balance);
+        if !merkle::verify_proof(&leaf, &self.proof, &tip.balances_root) {
+            return Err(format!(
+                "Balance {:.2} of {} is not committed by header #{}",
+                self.balance, self.address, tip.index
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// An account holder's signed acknowledgement of its balance as of a block
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BalanceAttestation {
+    pub address: String,
+    pub balance: f64,
+    /// Number of transactions the account has sent, standing in for a nonce
+    pub nonce: usize,
+    pub block_index: u32,
+    pub block_hash: String,
+    pub signature: Option<String>,
+}
+
+impl BalanceAttestation {
+    /// The bytes covered by the signature
+    fn message(&self) -> String {
+        format!(
+            "{}{}{}{}{}",
+            self.address, self.balance, self.nonce, self.block_index, self.block_hash
+        )
+    }
+
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<(), String> {
+        let signature = signer.sign_message(self.message().as_bytes())?;
+        self.signature = Some(hex::encode(signature));
+        Ok(())
+    }
+
+    /// Checks the signature against the attestation's current contents
+    pub fn verify_signature(&self, verifier: &dyn Verifier) -> bool {
+        match self.signature.as_ref().map(hex::decode) {
+            Some(Ok(bytes)) => verifier.verify_message(self.message().as_bytes(), &bytes).is_ok(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Blockchain, ChainConfig};
+    use crate::models::merkle::merkle_root;
+    use crate::models::{Block, Transaction};
+    use crate::models::signature::Signer;
+    use ed25519_dalek::Keypair;
+    use rand::rngs::OsRng;
+
+    fn chain_with_blocks() -> (Blockchain, String) {
+        let keypair = Keypair::generate(&mut OsRng);
+        let address = keypair.verifier().address();
+        let mut blockchain = Blockchain::new(&address);
+        blockchain.register_keypair(keypair);
+        blockchain.add_validator(address.clone()).unwrap();
+        blockchain.dev_mode = true;
+        blockchain.mine_empty_blocks(2, &address).unwrap();
+        (blockchain, address)
+    }
+
+    #[test]
+    fn balance_proof_verifies_against_trusted_headers() {
+        let (blockchain, address) = chain_with_blocks();
+        let proof = blockchain.balance_proof(&address).unwrap();
+        assert_eq!(proof.balance, blockchain.get_account_balance(&address));
+        assert!(proof.verify(&blockchain.export_headers()).is_ok());
+        assert!(blockchain.balance_proof("stranger").is_err());
+    }
+
+    #[test]
+    fn balance_proof_with_a_recomputed_balances_root_is_rejected() {
+        let (blockchain, address) = chain_with_blocks();
+        let mut proof = blockchain.balance_proof(&address).unwrap();
+
+        // Inflate the balance and rebuild the root so the Merkle path still checks out
+        proof.balance *= 10.0;
+        proof.header.balances_root = merkle_root(&[balance_leaf(&address, proof.balance)]);
+        proof.proof.clear();
+
+        let err = proof.verify(&blockchain.export_headers()).unwrap_err();
+        assert!(err.contains("trusted tip"), "{}", err);
+    }
+
+    /// A chain where the validator pays two accounts several times, with fees and one held transfer
+    fn chain_with_transfers() -> (Blockchain, String, String, String) {
+        let (mut blockchain, validator) = chain_with_blocks();
+        let config = ChainConfig { claim_threshold: Some(500.0), ..blockchain.config().clone() };
+        blockchain.set_config(config).unwrap();
+        let (first, second) = ("first".to_string(), "second".to_string());
+        let signer = blockchain.keypairs[&validator].clone();
+        for (recipient, amount, fee) in [(&first, 10.0, 0.5), (&second, 20.0, 0.0), (&first, 30.0, 1.0), (&second, 600.0, 0.0)] {
+            let transaction = Transaction::new(validator.clone(), recipient.clone(), amount).with_fee(fee);
+            blockchain.add_transaction(transaction, signer.as_ref()).unwrap();
+            blockchain.create_block(&validator).unwrap();
+        }
+        (blockchain, validator, first, second)
+    }
+
+    #[test]
+    fn balance_proofs_cover_every_transfer_and_held_claims() {
+        let (blockchain, validator, first, second) = chain_with_transfers();
+        let headers = blockchain.export_headers();
+        for address in [&validator, &first, &second] {
+            let proof = blockchain.balance_proof(address).unwrap();
+            assert_eq!(proof.balance, blockchain.get_account_balance(address), "{}", address);
+            proof.verify(&headers).unwrap();
+        }
+        // The held transfer is not the recipient's until claimed
+        assert_eq!(blockchain.balance_proof(&second).unwrap().balance, 20.0);
+        assert_eq!(blockchain.balance_proof(&first).unwrap().balance, 40.0);
+    }
+
+    #[test]
+    fn balance_proof_that_omits_a_debit_is_rejected() {
+        let (blockchain, validator, _, _) = chain_with_transfers();
+        let mut proof = blockchain.balance_proof(&validator).unwrap();
+        proof.balance += 30.0;
+
+        let err = proof.verify(&blockchain.export_headers()).unwrap_err();
+        assert!(err.contains("not committed"), "{}", err);
+    }
+
+    #[test]
+    fn balance_proof_must_reach_the_trusted_tip() {
+        let (mut blockchain, validator, first, _) = chain_with_transfers();
+        let stale = blockchain.balance_proof(&validator).unwrap();
+        let signer = blockchain.keypairs[&validator].clone();
+        blockchain.add_transaction(Transaction::new(validator.clone(), first, 50.0), signer.as_ref()).unwrap();
+        blockchain.create_block(&validator).unwrap();
+
+        let err = stale.verify(&blockchain.export_headers()).unwrap_err();
+        assert!(err.contains("trusted tip"), "{}", err);
+        assert!(stale.verify(&[]).is_err());
+    }
+
+    #[test]
+    fn header_chain_linkage_is_checked() {
+        let (blockchain, address) = chain_with_blocks();
+        let validators = vec![address];
+        let headers = blockchain.export_headers();
+        assert!(verify_header_chain(&headers, &validators).is_ok());
+        assert!(verify_header_chain(&[], &validators).is_err());
+        assert!(verify_header_chain(&headers[1..], &validators).is_err());
+
+        let gap = vec![headers[0].clone(), headers[2].clone()];
+        assert!(verify_header_chain(&gap, &validators).is_err());
+
+        let mut broken = headers.clone();
+        broken[2].previous_hash = "00".repeat(32);
+        assert!(verify_header_chain(&broken, &validators).is_err());
+    }
+
+    /// Signs a header the way `Block::sign` does, for forging headers without their blocks
+    fn resign(header: &mut BlockHeader, signer: &Keypair) {
+        let mut block = Block::with_timestamp(header.index, Vec::new(), header.previous_hash.clone(), header.validator.clone(), header.timestamp);
+        block.hash = header.hash.clone();
+        block.sign(signer).unwrap();
+        header.merkle_root = block.merkle_root();
+        header.validator_signature = block.validator_signature;
+    }
+
+    #[test]
+    fn forged_headers_are_rejected() {
+        let (blockchain, address) = chain_with_blocks();
+        let validators = vec![address.clone()];
+        let headers = blockchain.export_headers();
+        let forger = Keypair::generate(&mut OsRng);
+
+        // A different Merkle root, as a header for other transactions would carry
+        let mut forged = headers.clone();
+        forged[1].merkle_root = merkle_root(&["forged".to_string()]);
+        let err = verify_header_chain(&forged, &validators).unwrap_err();
+        assert!(err.contains("not signed"), "{}", err);
+
+        // Signed by another key in the validator's name
+        let mut forged = headers.clone();
+        resign(&mut forged[2], &forger);
+        let err = verify_header_chain(&forged, &validators).unwrap_err();
+        assert!(err.contains("Header #2 is not signed"), "{}", err);
+
+        // Properly signed by a producer outside the validator set
+        let mut forged = headers.clone();
+        forged[2].validator = forger.verifier().address();
+        resign(&mut forged[2], &forger);
+        let err = verify_header_chain(&forged, &validators).unwrap_err();
+        assert!(err.contains("not a validator"), "{}", err);
+
+        let mut unsigned = headers.clone();
+        unsigned[1].validator_signature = None;
+        assert!(verify_header_chain(&unsigned, &validators).is_err());
+
+        // A whole chain the forger produced links up but is signed by the wrong key
+        let mut own = Blockchain::new(&forger.verifier().address());
+        own.dev_mode = true;
+        let forger_address = own.register_keypair(Keypair::from_bytes(&forger.to_bytes()).unwrap());
+        own.add_validator(forger_address.clone()).unwrap();
+        own.mine_empty_blocks(2, &forger_address).unwrap();
+        assert!(verify_header_chain(&own.export_headers(), &[forger_address]).is_ok());
+        assert!(verify_header_chain(&own.export_headers(), &validators).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::core::blockchain::decode_keypair;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use ed25519_dalek::Keypair;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+
+const KDF_ROUNDS: u32 = 100_000;
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Encrypted keypair bundle written by `export_wallet`
+#[derive(Serialize, Deserialize)]
+struct WalletBundle {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    key
+}
+
+/// Encrypts keypairs (address -> "secret:public") into a JSON wallet bundle
+pub fn encrypt_wallet(keypairs: &HashMap<String, String>, passphrase: &str) -> Result<String, String> {
+    if passphrase.len() < MIN_PASSPHRASE_LEN {
+        return Err(format!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+
+    let plaintext = serde_json::to_vec(keypairs)
+        .map_err(|e| format!("Failed to serialize keypairs: {}", e))?;
+
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &salt))
+        .map_err(|_| "Failed to initialize cipher".to_string())?;
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+        .map_err(|_| "Failed to encrypt wallet".to_string())?;
+
+    let bundle = WalletBundle {
+        version: 1,
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    };
+    serde_json::to_string_pretty(&bundle).map_err(|e| format!("Failed to serialize wallet: {}", e))
+}
+
+/// Decrypts a wallet bundle, rejecting wrong passphrases and corrupt data
+pub fn decrypt_wallet(bundle_json: &str, passphrase: &str) -> Result<Vec<Keypair>, String> {
+    let corrupt = || "Wallet bundle is corrupt".to_string();
+
+    let bundle: WalletBundle = serde_json::from_str(bundle_json).map_err(|_| corrupt())?;
+    if bundle.version != 1 {
+        return Err(format!("Unsupported wallet version: {}", bundle.version));
+    }
+    let salt = hex::decode(&bundle.salt).map_err(|_| corrupt())?;
+    let nonce = hex::decode(&bundle.nonce).map_err(|_| corrupt())?;
+    let ciphertext = hex::decode(&bundle.ciphertext).map_err(|_| corrupt())?;
+    if nonce.len() != 12 {
+        return Err(corrupt());
+    }
+
+    let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &salt))
+        .map_err(|_| "Failed to initialize cipher".to_string())?;
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| "Wrong passphrase or corrupt wallet".to_string())?;
+
+    let encoded: HashMap<String, String> = serde_json::from_slice(&plaintext).map_err(|_| corrupt())?;
+    encoded
+        .iter()
+        .map(|(address, keypair_str)| decode_keypair(address, keypair_str))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Blockchain;
+    use crate::models::signature::{Signer, Verifier};
+
+    #[test]
+    fn wallet_round_trips_only_with_its_passphrase() {
+        let mut blockchain = Blockchain::new("genesis");
+        let mut publics = HashMap::new();
+        for _ in 0..3 {
+            let keypair = Keypair::generate(&mut OsRng);
+            let public = keypair.public;
+            publics.insert(blockchain.register_keypair(keypair), public);
+        }
+
+        let bundle = encrypt_wallet(&blockchain.encoded_keypairs(), "correct horse").unwrap();
+        let restored = decrypt_wallet(&bundle, "correct horse").unwrap();
+        assert_eq!(restored.len(), 3);
+        for keypair in &restored {
+            let address = keypair.verifier().address();
+            let signature = keypair.sign_message(address.as_bytes()).unwrap();
+            publics[&address].verify_message(address.as_bytes(), &signature).unwrap();
+        }
+
+        assert!(decrypt_wallet(&bundle, "wrong horse").is_err());
+        assert!(decrypt_wallet("{}", "correct horse").is_err());
+        assert!(encrypt_wallet(&blockchain.encoded_keypairs(), "short").is_err());
+    }
+}
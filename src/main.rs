@@ -1,24 +1,84 @@
 mod models;
 mod core;
 mod cli;
+mod network;
 
-use cli::blockchain_cli::BlockchainCLI;
+use cli::args::{self, USAGE};
+use cli::blockchain_cli::{BlockchainCLI, CliOptions};
+use cli::profiles::{self, Profile};
+use cli::watch;
+use core::blockchain::SaveRetry;
 use std::env;
+use std::process;
+use std::time::Duration;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Vec<String> = env::args().collect();
+    let port = env::var("PORT").ok();
+    let args = match args::parse_args(env::args().skip(1), port.as_deref()) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
 
-    let default_blockchain_file = "blockchain.json".to_string();
-    let blockchain_file = args.get(1).unwrap_or(&default_blockchain_file);
+    if let Some(name) = &args.save_profile {
+        profiles::save_profile(name, Profile {
+            blockchain_file: args.blockchain_file.clone(),
+            accounts_file: args.account_file.clone(),
+        })?;
+        println!("Saved profile '{}': {} / {}", name, args.blockchain_file, args.account_file);
+    }
 
-    let default_account_file = "accounts.json".to_string();
-    let account_file = args.get(2).unwrap_or(&default_account_file);
+    if args.has_flag("--list-profiles") {
+        let profiles = profiles::load_profiles()?;
+        if profiles.is_empty() {
+            println!("No profiles saved in {}", profiles::PROFILES_FILE);
+        }
+        for (name, profile) in profiles {
+            println!("{}: {} / {}", name, profile.blockchain_file, profile.accounts_file);
+        }
+        return Ok(());
+    }
 
-    let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
+    if args.has_flag("--watch") {
+        let blockchain_file = match &args.profile {
+            Some(name) => profiles::resolve(name)?.blockchain_file,
+            None => args.blockchain_file.clone(),
+        };
+        watch::watch_blocks(&blockchain_file, watch::POLL_INTERVAL)?;
+        return Ok(());
+    }
 
-    println!("Starting blockchain node on port: {}", port);
+    let options = CliOptions {
+        show_progress: args.has_flag("--progress"),
+        force: args.has_flag("--force"),
+        accounts_passphrase: env::var("ACCOUNTS_PASSPHRASE").ok(),
+        init_accounts_signature: args.has_flag("--init-accounts-signature"),
+        observer: args.has_flag("--observer"),
+        profile: args.profile.clone(),
+        assume_yes: args.has_flag("--yes"),
+        no_auto_validator: args.has_flag("--no-auto-validator"),
+        dev: args.has_flag("--dev"),
+        verbose_validation: args.has_flag("--debug"),
+        genesis_file: args.genesis_file.clone(),
+        event_log_file: args.event_log_file.clone(),
+        save_retry: {
+            let defaults = SaveRetry::default();
+            SaveRetry {
+                retries: args.save_retries.unwrap_or(defaults.retries),
+                backoff: args.save_backoff_ms.map(Duration::from_millis).unwrap_or(defaults.backoff),
+            }
+        },
+    };
+
+    println!("Starting blockchain node on port: {}", args.port);
     
-    let mut cli = BlockchainCLI::new(blockchain_file, account_file);
+    let mut cli = BlockchainCLI::new(&args.blockchain_file, &args.account_file, options)?;
+    if args.has_flag("--serve") {
+        let address = network::spawn_server(args.port, cli.blockchain_file().to_string())?;
+        println!("Serving the chain API on {}", address);
+    }
     cli.run();
     
     Ok(())
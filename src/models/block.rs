@@ -1,3 +1,5 @@
+use crate::models::merkle::{self, ProofStep};
+use crate::models::signature::{Signer, Verifier};
 use crate::models::transaction::Transaction;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -13,6 +15,58 @@ pub struct Block {
     pub previous_hash: String,
     pub hash: String,
     pub validator: String,
+    /// The validator's signature over the header (absent on blocks from before headers were signed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator_signature: Option<String>,
+}
+
+/// Block fields without the transactions, enough to check links, producers and Merkle proofs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub index: u32,
+    pub timestamp: u64,
+    pub previous_hash: String,
+    pub hash: String,
+    pub validator: String,
+    pub merkle_root: String,
+    /// Merkle root over every account balance after this block, sorted by address
+    ///
+    /// Not covered by the validator's signature, since only a replay of the chain can compute it.
+    #[serde(default)]
+    pub balances_root: String,
+    #[serde(default)]
+    pub validator_signature: Option<String>,
+}
+
+/// Hash a validator signs to vouch for a block, built from header fields only
+///
+/// The transactions are covered through the Merkle root, so a light client can check
+/// the signature without them. Only the validator, which comes last, can contain ':'.
+fn header_signing_hash(index: u32, timestamp: u64, previous_hash: &str, hash: &str, merkle_root: &str, validator: &str) -> String {
+    let data = format!("{}:{}:{}:{}:{}:{}", index, timestamp, previous_hash, hash, merkle_root, validator);
+    format!("{:x}", Sha256::digest(data.as_bytes()))
+}
+
+fn verify_signature_hex(signature: Option<&String>, message: &str, verifier: &dyn Verifier) -> bool {
+    match signature.map(hex::decode) {
+        Some(Ok(bytes)) => verifier.verify_message(message.as_bytes(), &bytes).is_ok(),
+        _ => false,
+    }
+}
+
+impl BlockHeader {
+    /// Checks the validator's signature against the header's current contents
+    pub fn verify_signature(&self, verifier: &dyn Verifier) -> bool {
+        let message = header_signing_hash(
+            self.index,
+            self.timestamp,
+            &self.previous_hash,
+            &self.hash,
+            &self.merkle_root,
+            &self.validator,
+        );
+        verify_signature_hex(self.validator_signature.as_ref(), &message, verifier)
+    }
 }
 
 impl Block {
@@ -22,17 +76,30 @@ impl Block {
         transactions: Vec<Transaction>,
         previous_hash: String,
         validator: String,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self::with_timestamp(index, transactions, previous_hash, validator, timestamp)
+    }
+
+    /// Creates a new block with an explicit timestamp
+    pub fn with_timestamp(
+        index: u32,
+        transactions: Vec<Transaction>,
+        previous_hash: String,
+        validator: String,
+        timestamp: u64,
     ) -> Self {
         let mut block = Block {
             index,
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            timestamp,
             transactions,
             previous_hash,
             hash: String::new(),
             validator,
+            validator_signature: None,
         };
 
         block.hash = block.calculate_hash();
@@ -55,6 +122,64 @@ impl Block {
     }
 }
 
+impl Block {
+    /// Hashes of this block's transactions, used as Merkle leaves
+    fn transaction_hashes(&self) -> Vec<String> {
+        self.transactions.iter().map(|tx| tx.calculate_hash()).collect()
+    }
+
+    /// Computes the Merkle root over this block's transactions
+    pub fn merkle_root(&self) -> String {
+        merkle::merkle_root(&self.transaction_hashes())
+    }
+
+    fn signing_hash(&self) -> String {
+        header_signing_hash(
+            self.index,
+            self.timestamp,
+            &self.previous_hash,
+            &self.hash,
+            &self.merkle_root(),
+            &self.validator,
+        )
+    }
+
+    /// Signs the block's header as its validator; call once the hash is final
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<(), String> {
+        let signature = signer.sign_message(self.signing_hash().as_bytes())?;
+        self.validator_signature = Some(hex::encode(signature));
+        Ok(())
+    }
+
+    /// Checks the validator's signature against the block's current contents
+    pub fn verify_signature(&self, verifier: &dyn Verifier) -> bool {
+        verify_signature_hex(self.validator_signature.as_ref(), &self.signing_hash(), verifier)
+    }
+
+    /// Header of this block, carrying its Merkle root in place of the transactions
+    ///
+    /// The balances root comes from replaying the chain, which the block alone cannot do.
+    pub fn header(&self, balances_root: String) -> BlockHeader {
+        BlockHeader {
+            index: self.index,
+            timestamp: self.timestamp,
+            previous_hash: self.previous_hash.clone(),
+            hash: self.hash.clone(),
+            validator: self.validator.clone(),
+            merkle_root: self.merkle_root(),
+            balances_root,
+            validator_signature: self.validator_signature.clone(),
+        }
+    }
+
+    /// Builds an inclusion proof for the transaction with the given hash
+    pub fn merkle_proof(&self, tx_hash: &str) -> Option<Vec<ProofStep>> {
+        let leaves = self.transaction_hashes();
+        let index = leaves.iter().position(|leaf| leaf == tx_hash)?;
+        merkle::merkle_proof(&leaves, index)
+    }
+}
+
 impl fmt::Display for Block {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -68,4 +193,32 @@ impl fmt::Display for Block {
             self.timestamp
         )
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::PublicKey;
+
+    // A block written by the build before fees, memos and claims were added
+    const BASELINE_BLOCK: &str = r#"{"index": 1, "timestamp": 1792172321, "transactions": [{"sender": "87e7a28cf35a629bc013f9fbfb4d7a9e80a5ad18702e91341341a1b41d8b0e17", "recipient": "11837b844dfa7757b1f1b9b067b4fc468978d40fa707f07b1bf9882fa54077cd", "amount": 12.5, "signature": "9cc31100d0d8b9dcb8c33ccd68d55ac8ddbd50815674086ccc7a7e0106e18ad26f02fad32af005070c794124d70ba721b2e8c3e948b515596fb25d0616c9490a", "timestamp": 1792172321}, {"sender": "87e7a28cf35a629bc013f9fbfb4d7a9e80a5ad18702e91341341a1b41d8b0e17", "recipient": "87e7a28cf35a629bc013f9fbfb4d7a9e80a5ad18702e91341341a1b41d8b0e17", "amount": 3.0, "signature": "3ba86e6eb40dc09f09c2dcee4679da745719718bc9787baf814984d3940957910bbaf76e9eedabdf912197dd8c808a8d87f825fbaa79122329a5cc468a49c006", "timestamp": 1792172321}], "previous_hash": "ac660fbc8dbf06940cadddd55728802fadbf8db1c65a2c09a65ca1b0dd5aefad", "hash": "6e43875f0943bd71e2e5dfba7beafdb1e81b65ac87deba14aaf71046f3203b89", "validator": "87e7a28cf35a629bc013f9fbfb4d7a9e80a5ad18702e91341341a1b41d8b0e17"}"#;
+
+    #[test]
+    fn baseline_block_keeps_its_hash() {
+        let block: Block = serde_json::from_str(BASELINE_BLOCK).unwrap();
+        assert_eq!(block.calculate_hash(), block.hash);
+
+        let serialized = serde_json::to_string(&block.transactions).unwrap();
+        assert!(!serialized.contains("fee"));
+        assert!(!serialized.contains("memo"));
+        assert!(!serialized.contains("claim_of"));
+    }
+
+    #[test]
+    fn baseline_signatures_still_verify() {
+        let block: Block = serde_json::from_str(BASELINE_BLOCK).unwrap();
+        for tx in &block.transactions {
+            let public_key = PublicKey::from_bytes(&hex::decode(&tx.sender).unwrap()).unwrap();
+            assert!(tx.verify_signature(&public_key));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use sha2::{Digest, Sha256};
+
+/// One sibling hash on the path from a leaf to the Merkle root
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofStep {
+    pub hash: String,
+    /// Whether the sibling sits to the left of the running hash
+    pub is_left: bool,
+}
+
+// Domain tags keep a leaf from ever hashing like an internal node and vice versa
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+
+fn hash_leaf(leaf: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_TAG]);
+    hasher.update(leaf.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn hash_pair(left: &str, right: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_TAG]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Combines one tree level into the next, promoting the last node unchanged on odd counts
+///
+/// Duplicating it instead would give `[a, b, c]` and `[a, b, c, c]` the same root.
+fn next_level(level: &[String]) -> Vec<String> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            _ => pair[0].clone(),
+        })
+        .collect()
+}
+
+/// Computes the Merkle root over the given leaf hashes
+pub fn merkle_root(leaves: &[String]) -> String {
+    if leaves.is_empty() {
+        return format!("{:x}", Sha256::digest(b""));
+    }
+
+    let mut level: Vec<String> = leaves.iter().map(|leaf| hash_leaf(leaf)).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.remove(0)
+}
+
+/// Builds the inclusion proof for the leaf at `index`
+pub fn merkle_proof(leaves: &[String], mut index: usize) -> Option<Vec<ProofStep>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut level: Vec<String> = leaves.iter().map(|leaf| hash_leaf(leaf)).collect();
+    while level.len() > 1 {
+        let is_left = index % 2 == 1;
+        let sibling = if is_left { index - 1 } else { index + 1 };
+        // A promoted node has no sibling at this level
+        if sibling < level.len() {
+            proof.push(ProofStep { hash: level[sibling].clone(), is_left });
+        }
+
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Checks that a leaf and its proof hash up to the expected root
+pub fn verify_proof(leaf: &str, proof: &[ProofStep], root: &str) -> bool {
+    let computed = proof.iter().fold(hash_leaf(leaf), |running, step| {
+        if step.is_left {
+            hash_pair(&step.hash, &running)
+        } else {
+            hash_pair(&running, &step.hash)
+        }
+    });
+    computed == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("{:x}", Sha256::digest(i.to_string().as_bytes()))).collect()
+    }
+
+    #[test]
+    fn every_leaf_proves_against_the_root() {
+        for count in 1..=9 {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle_proof(&leaves, index).unwrap();
+                assert!(verify_proof(leaf, &proof, &root), "leaf {} of {}", index, count);
+            }
+            assert!(merkle_proof(&leaves, count).is_none());
+        }
+    }
+
+    #[test]
+    fn proofs_fail_for_other_leaves_and_roots() {
+        let leaves = leaves(5);
+        let root = merkle_root(&leaves);
+        let proof = merkle_proof(&leaves, 2).unwrap();
+        assert!(!verify_proof(&leaves[3], &proof, &root));
+        assert!(!verify_proof(&leaves[2], &proof, &merkle_root(&leaves[..4])));
+    }
+
+    #[test]
+    fn internal_nodes_cannot_pose_as_leaves() {
+        let leaves = leaves(4);
+        let root = merkle_root(&leaves);
+        let internal = vec![merkle_root(&leaves[..2]), merkle_root(&leaves[2..])];
+        assert_ne!(merkle_root(&internal), root);
+        assert!(!verify_proof(&internal[0], &[ProofStep { hash: internal[1].clone(), is_left: false }], &root));
+    }
+
+    #[test]
+    fn repeating_the_odd_leaf_changes_the_root() {
+        let leaves = leaves(3);
+        let mut padded = leaves.clone();
+        padded.push(leaves[2].clone());
+        assert_ne!(merkle_root(&leaves), merkle_root(&padded));
+        assert_eq!(merkle_proof(&leaves, 2).unwrap().len(), 1);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod transaction;
 pub mod block;
+pub mod merkle;
+pub mod signature;
 
 pub use transaction::Transaction;
-pub use block::Block;
\ No newline at end of file
+pub use block::{Block, BlockHeader};
@@ -0,0 +1,106 @@
+use ed25519_dalek::{Keypair, PublicKey, Signature};
+use hex;
+
+/// Produces signatures over transaction hashes
+///
+/// ed25519 is the default scheme; other schemes plug in by implementing this
+/// together with [`Verifier`] for their public key type.
+pub trait Signer {
+    fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, String>;
+
+    /// Verifier matching this signer's secret key
+    fn verifier(&self) -> Box<dyn Verifier>;
+}
+
+/// Checks signatures produced by the matching [`Signer`]
+pub trait Verifier {
+    /// Explains why the signature was rejected on failure
+    fn verify_message(&self, message: &[u8], signature: &[u8]) -> Result<(), String>;
+
+    /// Address derived from the public key
+    fn address(&self) -> String;
+}
+
+impl Signer for Keypair {
+    fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(ed25519_dalek::Signer::sign(self, message).to_bytes().to_vec())
+    }
+
+    fn verifier(&self) -> Box<dyn Verifier> {
+        Box::new(self.public)
+    }
+}
+
+impl Verifier for PublicKey {
+    fn verify_message(&self, message: &[u8], signature: &[u8]) -> Result<(), String> {
+        let signature = Signature::from_bytes(signature)
+            .map_err(|_| "Failed to convert signature bytes into a valid ed25519 signature.".to_string())?;
+        ed25519_dalek::Verifier::verify(self, message, &signature)
+            .map_err(|_| "Signature verification failed.".to_string())
+    }
+
+    fn address(&self) -> String {
+        hex::encode(self.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Transaction;
+    use sha2::{Digest, Sha256};
+
+    /// Toy scheme: the "signature" is a keyed hash, and the verifier shares the key
+    #[derive(Clone)]
+    struct MockKey(&'static str);
+
+    impl MockKey {
+        fn tag(&self, message: &[u8]) -> Vec<u8> {
+            Sha256::new().chain_update(self.0).chain_update(message).finalize().to_vec()
+        }
+    }
+
+    impl Signer for MockKey {
+        fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>, String> {
+            Ok(self.tag(message))
+        }
+
+        fn verifier(&self) -> Box<dyn Verifier> {
+            Box::new(self.clone())
+        }
+    }
+
+    impl Verifier for MockKey {
+        fn verify_message(&self, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            if self.tag(message) == signature {
+                Ok(())
+            } else {
+                Err("Mock tag mismatch.".to_string())
+            }
+        }
+
+        fn address(&self) -> String {
+            format!("mock-{}", self.0)
+        }
+    }
+
+    #[test]
+    fn transactions_sign_and_verify_through_any_scheme() {
+        let key = MockKey("alice");
+        let mut transaction = Transaction::new(key.verifier().address(), "bob".to_string(), 10.0);
+        transaction.sign(&key).unwrap();
+
+        let received: Transaction = serde_json::from_str(&serde_json::to_string(&transaction).unwrap()).unwrap();
+        let verifier = key.verifier();
+        assert!(received.verify_signature(verifier.as_ref()));
+        assert!(received.check_validity(Some(verifier.as_ref())).is_ok());
+
+        assert!(!received.verify_signature(&MockKey("mallory")));
+        let ed25519 = Keypair::generate(&mut rand::rngs::OsRng);
+        assert!(!received.verify_signature(&ed25519.public));
+
+        let mut altered = received;
+        altered.amount = 1000.0;
+        assert!(!altered.verify_signature(verifier.as_ref()));
+    }
+}
@@ -1,10 +1,9 @@
-use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use crate::models::signature::{Signer, Verifier};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
 use std::fmt;
+use std::io::Write;
 use std::time::{SystemTime, UNIX_EPOCH};
-use std::fs;
 use hex;
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -14,6 +13,14 @@ pub struct Transaction {
     pub amount: f64,
     pub signature: Option<String>,
     pub timestamp: u64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub fee: f64,
+    /// Free-form reference attached by the sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Hash of a held transfer this transaction claims for its sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_of: Option<String>,
 }
 
 impl Transaction {
@@ -27,133 +34,233 @@ impl Transaction {
                 .duration_since(UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
+            fee: 0.0,
+            memo: None,
+            claim_of: None,
         }
     }
 
+    /// Sets the fee paid to the validator that includes this transaction
+    pub fn with_fee(mut self, fee: f64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Attaches a reference to the transaction; blank memos are dropped
+    pub fn with_memo(mut self, memo: &str) -> Self {
+        let memo = memo.trim();
+        self.memo = if memo.is_empty() { None } else { Some(memo.to_string()) };
+        self
+    }
+
+    /// Builds the zero-amount transaction a recipient signs to claim a held transfer
+    pub fn claim(claimant: String, transfer_hash: &str) -> Self {
+        let mut transaction = Transaction::new(claimant.clone(), claimant, 0.0);
+        transaction.claim_of = Some(transfer_hash.to_string());
+        transaction
+    }
+
     pub fn calculate_hash(&self) -> String {
-        let transaction_data = format!(
-            "{}{}{}{}",
-            self.sender, self.recipient, self.amount, self.timestamp
-        );
+        let transaction_data = if self.fee == 0.0 && self.memo.is_none() && self.claim_of.is_none() {
+            // Transactions without the newer fields keep their original hash and signature
+            format!("{}{}{}{}", self.sender, self.recipient, self.amount, self.timestamp)
+        } else {
+            // Plain concatenation would let a relayer move characters between the fee, memo
+            // and claimed hash without breaking the signature, so every field is length-prefixed
+            let mut data = String::from("tx/v2;");
+            for field in [
+                Some(self.sender.clone()),
+                Some(self.recipient.clone()),
+                Some(self.amount.to_string()),
+                Some(self.timestamp.to_string()),
+                Some(self.fee.to_string()),
+                self.memo.clone(),
+                self.claim_of.clone(),
+            ] {
+                push_field(&mut data, field.as_deref());
+            }
+            data
+        };
         let mut hasher = Sha256::new();
         hasher.update(transaction_data.as_bytes());
         format!("{:x}", hasher.finalize())
     }
 
-    pub fn sign(&mut self, keypair: &Keypair) -> Result<(), &'static str> {
+    pub fn sign(&mut self, signer: &dyn Signer) -> Result<(), String> {
         if self.sender == "0" {
-            return Err("Cannot sign genesis transaction");
+            return Err("Transactions from the minting address \"0\" carry no signature and cannot be signed".to_string());
         }
 
         let transaction_hash = self.calculate_hash();
-        let signature = keypair.sign(transaction_hash.as_bytes());
-        self.signature = Some(hex::encode(signature.to_bytes()));
+        let signature = signer.sign_message(transaction_hash.as_bytes())?;
+        self.signature = Some(hex::encode(signature));
         Ok(())
     }
 
-    fn load_accounts() -> HashMap<String, String> {
-        let file_content = fs::read_to_string("accounts.json")
-            .expect("Unable to read file");
-
-        let accounts: HashMap<String, String> = serde_json::from_str(&file_content)
-            .expect("Unable to parse JSON");
+    /// Checks the signature against the transaction's current contents
+    pub fn verify_signature(&self, verifier: &dyn Verifier) -> bool {
+        match self.signature.as_ref().map(hex::decode) {
+            Some(Ok(bytes)) => verifier
+                .verify_message(self.calculate_hash().as_bytes(), &bytes)
+                .is_ok(),
+            _ => false,
+        }
+    }
 
-        accounts
+    /// Verifies the transaction using the sender's public key held in memory
+    ///
+    /// The reason for the decision is written to `log` when one is given.
+    pub fn is_valid(&self, verifier: Option<&dyn Verifier>, log: Option<&mut dyn Write>) -> bool {
+        let result = self.check_validity(verifier);
+        if let Some(log) = log {
+            // The log is diagnostic only, so failing to write it does not change the outcome
+            let _ = match &result {
+                Ok(reason) => writeln!(log, "{}", reason),
+                Err(reason) => writeln!(log, "{}", reason),
+            };
+        }
+        result.is_ok()
     }
 
-    pub fn is_valid(&self) -> bool {
+    /// Verifies the transaction, describing why it was accepted or rejected
+    pub fn check_validity(&self, verifier: Option<&dyn Verifier>) -> Result<&'static str, String> {
         // Genesis transactions are always valid
         if self.sender == "0" {
-            println!("Transaction is a genesis transaction, always valid.");
-            return true;
+            return Ok("Transaction is a genesis transaction, always valid.");
         }
 
         // Must have a signature
-        let signature = match &self.signature {
-            Some(sig) => {
-                sig
-            },
-            None => {
-                println!("Transaction has no signature.");
-                return false;
-            },
-        };
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| "Transaction has no signature.".to_string())?;
 
-        // Load public keys from the accounts file
-        let accounts = Self::load_accounts();
-
-        // Look up the sender's public key
-        let public_key_data = match accounts.get(&self.sender) {
-            Some(data) => data,
-            None => {
-                println!("Sender not found in accounts.");
-                return false;
-            },
-        };
-
-        // Extract the public key part (assuming the format is "public_key:secret_key")
-        let parts: Vec<&str> = public_key_data.split(':').collect();
-        if parts.len() != 2 {
-            println!("Invalid public key data format.");
-            return false;
-        }
-
-        let public_key_str = parts[1];
-
-        // Convert the public key from string to ed25519 PublicKey
-        let public_key = match PublicKey::from_bytes(&hex::decode(public_key_str).unwrap()) {
-            Ok(pk) => pk,
-            Err(_) => {
-                println!("Failed to parse public key.");
-                return false;
-            },
-        };
+        // Sender's public key must be known
+        let verifier = verifier.ok_or_else(|| "Sender not found in accounts.".to_string())?;
 
         // Signature must be valid hex
-        let signature_bytes = match hex::decode(signature) {
-            Ok(bytes) => bytes,
-            Err(_) => {
-                println!("Failed to decode signature as hex.");
-                return false;
-            },
-        };
+        let signature_bytes = hex::decode(signature)
+            .map_err(|_| "Failed to decode signature as hex.".to_string())?;
 
-        // Signature must be valid ed25519
-        let signature = match Signature::from_bytes(&signature_bytes) {
-            Ok(sig) => sig,
-            Err(_) => {
-                println!("Failed to convert signature bytes into a valid ed25519 signature.");
-                return false;
-            },
-        };
-        
         // Calculate transaction hash
         let transaction_hash = self.calculate_hash();
 
-        let result = public_key.verify(transaction_hash.as_bytes(), &signature);
-        if result.is_ok() {
-            println!("Signature is valid.");
-        } else {
-            println!("Signature verification failed.");
-        }
-
-        // Return the result of the verification
-        result.is_ok()
+        verifier.verify_message(transaction_hash.as_bytes(), &signature_bytes)?;
+        Ok("Signature is valid.")
+    }
+}
 
+/// Appends a field as `<length>:<value>;`, or `-;` when absent, so field boundaries are unambiguous
+fn push_field(data: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => data.push_str(&format!("{}:{};", value.len(), value)),
+        None => data.push_str("-;"),
     }
+}
 
+// Zero fees are left out of the JSON so blocks from before fees existed keep their hashes
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
 }
 
 impl fmt::Display for Transaction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "From: {}\nTo: {}\nAmount: {:.2}\nTimestamp: {}\nSigned: {}",
+            "From: {}\nTo: {}\nAmount: {:.2}\nFee: {:.2}\nTimestamp: {}\nSigned: {}",
             self.sender,
             self.recipient,
             self.amount,
+            self.fee,
             self.timestamp,
             self.signature.is_some()
-        )
+        )?;
+        if let Some(memo) = &self.memo {
+            write!(f, "\nMemo: {}", memo)?;
+        }
+        if let Some(claim_of) = &self.claim_of {
+            write!(f, "\nClaims: {}", claim_of)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Keypair;
+    use rand::rngs::OsRng;
+
+    fn transfer(sender: &str) -> Transaction {
+        let mut transaction = Transaction::new(sender.to_string(), "recipient".to_string(), 10.0);
+        transaction.timestamp = 1_700_000_000;
+        transaction
+    }
+
+    #[test]
+    fn plain_transfers_keep_their_original_hash() {
+        let transaction = transfer("sender");
+        let expected = format!("{:x}", Sha256::digest(b"senderrecipient101700000000"));
+        assert_eq!(transaction.calculate_hash(), expected);
+    }
+
+    #[test]
+    fn fee_and_memo_cannot_trade_characters() {
+        // Under plain concatenation both hashed "...1" + "5"
+        let original = transfer("sender").with_fee(1.0).with_memo("5");
+        let rewritten = transfer("sender").with_fee(15.0);
+        assert_ne!(original.calculate_hash(), rewritten.calculate_hash());
+    }
+
+    #[test]
+    fn signature_covers_fee_and_memo() {
+        let keypair = Keypair::generate(&mut OsRng);
+        let mut signed = transfer(&keypair.verifier().address()).with_fee(1.0).with_memo("invoice 42");
+        signed.sign(&keypair).unwrap();
+        assert!(signed.verify_signature(&keypair.public));
+
+        let mut relayed = signed.clone();
+        relayed.fee = 2.0;
+        assert!(!relayed.verify_signature(&keypair.public));
+
+        let mut relayed = signed.clone();
+        relayed.memo = Some("invoice 43".to_string());
+        assert!(!relayed.verify_signature(&keypair.public));
+
+        let mut relayed = signed;
+        relayed.memo = None;
+        assert!(!relayed.verify_signature(&keypair.public));
+    }
+
+    #[test]
+    fn memo_and_claimed_hash_cannot_trade_characters() {
+        let mut original = transfer("sender").with_memo("ab");
+        original.claim_of = Some("cd".to_string());
+        let mut rewritten = transfer("sender").with_memo("a");
+        rewritten.claim_of = Some("bcd".to_string());
+        assert_ne!(original.calculate_hash(), rewritten.calculate_hash());
+
+        // A claim must not hash like a transfer whose memo is the claimed hash
+        let mut claim = transfer("sender");
+        claim.claim_of = Some("cd".to_string());
+        assert_ne!(claim.calculate_hash(), transfer("sender").with_memo("cd").calculate_hash());
+    }
+
+
+    #[test]
+    fn validation_reasons_go_only_to_a_given_log() {
+        let keypair = Keypair::generate(&mut OsRng);
+        let mut signed = transfer(&keypair.verifier().address());
+        signed.sign(&keypair).unwrap();
+
+        let mut log = Vec::new();
+        assert!(signed.is_valid(Some(&keypair.public), Some(&mut log)));
+        assert_eq!(String::from_utf8(log).unwrap(), "Signature is valid.\n");
+
+        let mut log = Vec::new();
+        assert!(!transfer("sender").is_valid(None, Some(&mut log)));
+        assert_eq!(String::from_utf8(log).unwrap(), "Transaction has no signature.\n");
+
+        assert!(signed.is_valid(Some(&keypair.public), None));
     }
 }
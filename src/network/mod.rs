@@ -0,0 +1,5 @@
+pub mod peer;
+pub mod server;
+
+pub use peer::fetch_chain;
+pub use server::spawn_server;
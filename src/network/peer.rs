@@ -0,0 +1,146 @@
+use crate::models::Block;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Largest response accepted from a peer, so a misbehaving node cannot exhaust memory
+const MAX_RESPONSE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Fetches a remote node's chain from its `GET /chain` endpoint, or from the path given in the URL
+pub fn fetch_chain(url: &str) -> Result<Vec<Block>, String> {
+    let (host, port, path) = parse_url(url)?;
+
+    let mut stream = TcpStream::connect((host.as_str(), port))
+        .map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+    let timeout = Some(Duration::from_secs(10));
+    stream
+        .set_read_timeout(timeout)
+        .and_then(|_| stream.set_write_timeout(timeout))
+        .map_err(|e| format!("Failed to configure connection: {}", e))?;
+
+    // HTTP/1.0 keeps the response body unchunked and closes the connection when done
+    let request = format!("GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, host);
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let mut response = String::new();
+    let read = stream
+        .take(MAX_RESPONSE_BYTES + 1)
+        .read_to_string(&mut response)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    if read as u64 > MAX_RESPONSE_BYTES {
+        return Err(format!("Peer response exceeds {} bytes", MAX_RESPONSE_BYTES));
+    }
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| "Malformed HTTP response".to_string())?;
+    let status_line = head.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("Peer responded with: {}", status_line));
+    }
+
+    serde_json::from_str(body).map_err(|_| "Failed to parse remote chain".to_string())
+}
+
+/// Splits an `http://host[:port][/path]` URL into host, port and request path
+fn parse_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("Only http:// URLs are supported: {}", url))?;
+
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, ""),
+    };
+    // A bare node address means its chain endpoint
+    let path = match path.trim_end_matches('/') {
+        "" => "/chain".to_string(),
+        path => path.to_string(),
+    };
+
+    let (host, port) = match authority.split_once(':') {
+        Some((host, port)) => {
+            let port = port
+                .parse::<u16>()
+                .map_err(|_| format!("Invalid port in URL: {}", url))?;
+            (host, port)
+        }
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(format!("Missing host in URL: {}", url));
+    }
+
+    Ok((host.to_string(), port, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Blockchain;
+    use crate::models::signature::Signer;
+    use crate::network::spawn_server;
+    use ed25519_dalek::Keypair;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn parse_url_defaults_to_the_chain_endpoint() {
+        assert_eq!(parse_url("http://node:8080").unwrap(), ("node".to_string(), 8080, "/chain".to_string()));
+        assert_eq!(parse_url("http://node/").unwrap(), ("node".to_string(), 80, "/chain".to_string()));
+    }
+
+    #[test]
+    fn parse_url_keeps_an_explicit_path() {
+        assert_eq!(
+            parse_url("http://127.0.0.1:3000/chain").unwrap(),
+            ("127.0.0.1".to_string(), 3000, "/chain".to_string())
+        );
+        assert_eq!(
+            parse_url("http://node:3000/api/v1/chain/").unwrap(),
+            ("node".to_string(), 3000, "/api/v1/chain".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_url_rejects_bad_input() {
+        assert!(parse_url("https://node").is_err());
+        assert!(parse_url("http://node:3000x/chain").is_err());
+        assert!(parse_url("http://:3000").is_err());
+    }
+
+    /// Saves the chain to a fresh file and serves it, returning the file and its chain URL
+    fn serve(blockchain: &Blockchain, name: &str) -> (String, String) {
+        let dir = std::env::temp_dir().join(format!("blockchain-peer-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let chain_file = dir.join("chain.json").to_string_lossy().into_owned();
+        blockchain.save_to_file(&chain_file, &dir.join("accounts.json").to_string_lossy()).unwrap();
+        let address = spawn_server(0, chain_file.clone()).unwrap();
+        (chain_file, format!("http://127.0.0.1:{}/chain", address.port()))
+    }
+
+    #[test]
+    fn syncing_adopts_only_longer_valid_peer_chains() {
+        let keypair = Keypair::generate(&mut OsRng);
+        let validator = keypair.verifier().address();
+        let mut remote = Blockchain::new(&validator);
+        remote.register_keypair(keypair);
+        remote.add_validator(validator.clone()).unwrap();
+        remote.dev_mode = true;
+        let (genesis_file, genesis_only) = serve(&remote, "genesis");
+        remote.mine_empty_blocks(2, &validator).unwrap();
+        let (_, longer) = serve(&remote, "longer");
+        remote.chain[1].timestamp += 1;
+        let (_, tampered) = serve(&remote, "tampered");
+
+        let mut local = Blockchain::load_read_only(&genesis_file).unwrap();
+        assert!(local.try_replace_chain(fetch_chain(&tampered).unwrap()).is_err());
+        assert_eq!(local.chain.len(), 1);
+
+        assert!(local.try_replace_chain(fetch_chain(&longer).unwrap()).unwrap());
+        assert_eq!(local.chain.len(), 3);
+        assert!(!local.try_replace_chain(fetch_chain(&genesis_only).unwrap()).unwrap());
+        assert_eq!(local.chain.len(), 3);
+    }
+}
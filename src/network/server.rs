@@ -0,0 +1,156 @@
+use crate::core::Blockchain;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+/// Most bytes read from a request; only the request line and headers are expected
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+
+/// Starts answering read-only JSON requests about the chain file on a background thread
+///
+/// Every request reloads the file, so responses reflect the last save. Endpoints:
+/// `GET /chain` returns the blocks, `GET /receipt/<tx hash>` a confirmation receipt and
+/// `GET /fingerprint` the chain fingerprint with the height it covers.
+///
+/// Returns the address actually bound, which differs from the request for port 0.
+pub fn spawn_server(port: u16, blockchain_file: String) -> Result<SocketAddr, String> {
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to read the listening address: {}", e))?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // A client that hangs up early only affects its own response
+            let _ = handle_connection(stream, &blockchain_file);
+        }
+    });
+    Ok(address)
+}
+
+fn handle_connection(mut stream: TcpStream, blockchain_file: &str) -> std::io::Result<()> {
+    let timeout = Some(Duration::from_secs(10));
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+
+    // Read the headers too, so closing the connection does not reset it before the client reads
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let (status, body) = match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["GET", path, _] => match Blockchain::load_read_only(blockchain_file) {
+            Ok(blockchain) => route(path, &blockchain),
+            Err(e) => (503, error_body(&e)),
+        },
+        [_, _, _] => (405, error_body("Only GET is supported")),
+        _ => (400, error_body("Malformed request")),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.0 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason_phrase(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Answers a GET request for the given path with a status code and JSON body
+fn route(path: &str, blockchain: &Blockchain) -> (u16, String) {
+    if path == "/chain" {
+        return to_json(&blockchain.chain);
+    }
+    if path == "/fingerprint" {
+        return to_json(&serde_json::json!({
+            "height": blockchain.get_latest_block().index,
+            "fingerprint": blockchain.fingerprint(),
+        }));
+    }
+    if let Some(tx_hash) = path.strip_prefix("/receipt/") {
+        return match blockchain.receipt(tx_hash) {
+            Some(receipt) => to_json(&receipt),
+            None => (404, error_body(&format!("Transaction {} is not confirmed", tx_hash))),
+        };
+    }
+    (404, error_body(&format!("No endpoint at {}", path)))
+}
+
+fn to_json(value: &impl Serialize) -> (u16, String) {
+    match serde_json::to_string(value) {
+        Ok(body) => (200, body),
+        Err(e) => (500, error_body(&format!("Failed to serialize response: {}", e))),
+    }
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Block;
+    use crate::network::fetch_chain;
+    use std::fs;
+    use std::process;
+
+    #[test]
+    fn receipts_are_served_for_confirmed_transactions() {
+        let blockchain = Blockchain::new("genesis");
+        let tx_hash = blockchain.chain[0].transactions[0].calculate_hash();
+
+        let (status, body) = route(&format!("/receipt/{}", tx_hash), &blockchain);
+        assert_eq!(status, 200);
+        let receipt: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(receipt["block_index"], 0);
+        assert_eq!(receipt["block_hash"], blockchain.chain[0].hash.as_str());
+
+        assert_eq!(route("/receipt/unknown", &blockchain).0, 404);
+        assert_eq!(route("/missing", &blockchain).0, 404);
+    }
+
+    #[test]
+    fn fingerprint_is_served_with_its_height() {
+        let blockchain = Blockchain::new("genesis");
+        let (status, body) = route("/fingerprint", &blockchain);
+        assert_eq!(status, 200);
+        let response: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(response["height"], 0);
+        assert_eq!(response["fingerprint"], blockchain.fingerprint().as_str());
+    }
+
+    #[test]
+    fn peers_can_fetch_the_served_chain() {
+        let dir = std::env::temp_dir().join(format!("blockchain-server-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let chain_file = dir.join("chain.json").to_string_lossy().into_owned();
+        let blockchain = Blockchain::new("genesis");
+        blockchain.save_to_file(&chain_file, &dir.join("accounts.json").to_string_lossy()).unwrap();
+
+        let address = spawn_server(0, chain_file).unwrap();
+        let fetched: Vec<Block> = fetch_chain(&format!("http://127.0.0.1:{}/chain", address.port())).unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].hash, blockchain.chain[0].hash);
+    }
+}